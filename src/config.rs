@@ -1,11 +1,19 @@
-use crate::Result;
-use serde::{Deserialize, Serialize};
+use crate::core::curve::CurveType;
+use crate::core::keymap::find_conflicts;
+use crate::core::keys::{Binding, KeyCombo};
+use crate::{Error, Result};
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
   pub motion: MotionConfig,
+  #[serde(default)]
+  pub scroll: ScrollConfig,
   pub keybindings: KeyBindings,
   pub modes: ModeConfig,
   pub system: SystemConfig,
@@ -13,36 +21,426 @@ pub struct Config {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MotionConfig {
-  /// Acceleration curve type: "sigmoid", "exponential", "linear"
-  pub curve_type: String,
-  /// Maximum pixels per second
+  /// Acceleration curve: "sigmoid", "exponential", "linear", a piecewise or bezier
+  /// table, or "libinput-adaptive"/"libinput-flat" to feel like a libinput mouse
+  pub curve_type: CurveType,
+  /// Maximum pixels per second, before libinput acceleration and per-axis scaling
   pub max_speed: f64,
+  /// Pixels per second right after a direction is pressed
+  #[serde(default = "default_initial_speed")]
+  pub initial_speed: f64,
+  /// Seconds of holding a direction to ramp from `initial_speed` to `max_speed`
+  #[serde(default = "default_ramp_time")]
+  pub ramp_time: f64,
+  /// Horizontal speed multiplier
+  #[serde(default = "default_speed_scale")]
+  pub speed_scale_x: f64,
+  /// Vertical speed multiplier
+  #[serde(default = "default_speed_scale")]
+  pub speed_scale_y: f64,
+  /// Caps horizontal speed in pixels per second, slowing the whole motion to keep its direction
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_speed_x: Option<f64>,
+  /// Caps vertical speed in pixels per second, slowing the whole motion to keep its direction
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_speed_y: Option<f64>,
   /// Acceleration factor (0.0-1.0)
   pub acceleration: f64,
-  /// Friction coefficient (0.0-1.0)
-  pub friction: f64,
+  /// libinput pointer speed setting (-1.0-1.0), used by the libinput curves
+  #[serde(default)]
+  pub libinput_speed: f64,
+  /// Rate at which velocity decays per second once keys are released (higher = stops sooner)
+  #[serde(default = "default_decay")]
+  pub decay: f64,
+  /// Deprecated per-frame velocity factor (0.0-1.0], converted into `decay` on load
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub friction: Option<f64>,
+  /// Seconds a fling glides before stopping; 0 disables gliding
+  #[serde(default = "default_coast_time")]
+  pub coast_time: f64,
+  /// Caps acceleration in pixels per second², switching from exponential smoothing
+  /// to a jerk-limited profile that also governs stopping in place of `decay`
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_acceleration: Option<f64>,
+  /// Caps the change of acceleration in pixels per second³; needs `max_acceleration`
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_jerk: Option<f64>,
+  /// Pixels per second the cursor must be moving at on release to glide
+  #[serde(default = "default_fling_speed")]
+  pub fling_speed: f64,
+  /// What happens when a direction is pressed against the cursor's motion
+  #[serde(default)]
+  pub reversal: Reversal,
+  /// Extra decay rate per second against the old motion, for `reversal = "brake"`
+  #[serde(default = "default_reversal_brake")]
+  pub reversal_brake: f64,
+  /// Mode that takes over after a reversal, for `reversal = "precise"`
+  #[serde(default = "default_reversal_mode")]
+  pub reversal_mode: String,
+  /// Seconds `reversal_mode` stays in effect after a reversal
+  #[serde(default = "default_reversal_time")]
+  pub reversal_time: f64,
   /// Target frames per second for motion engine
   pub target_fps: u32,
 }
 
+/// How the cursor turns around when the opposite direction is pressed mid-motion,
+/// as when correcting an overshoot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reversal {
+  /// Decelerate and turn at the usual acceleration rate
+  #[default]
+  Smooth,
+  /// Drop the motion against the new direction at once; with `max_acceleration`
+  /// the turn is only as quick as the jerk-limited profile allows
+  Instant,
+  /// Decelerate at `reversal_brake` until the cursor has turned
+  Brake,
+  /// Turn instantly, then move in `reversal_mode` for `reversal_time` seconds
+  Precise,
+}
+
+/// Physics for scroll mode, in wheel notches instead of pixels
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrollConfig {
+  /// Acceleration curve, as in `MotionConfig`
+  pub curve_type: CurveType,
+  /// Maximum wheel notches per second
+  pub max_speed: f64,
+  /// Wheel notches per second right after a direction is pressed
+  #[serde(default = "default_scroll_initial_speed")]
+  pub initial_speed: f64,
+  /// Seconds of holding a direction to ramp from `initial_speed` to `max_speed`
+  #[serde(default = "default_ramp_time")]
+  pub ramp_time: f64,
+  /// Acceleration factor (0.0-1.0)
+  pub acceleration: f64,
+  /// Velocity decay rate per second, as in `MotionConfig`; `friction` is read as the
+  /// same rate, as scroll never had a per-frame factor to convert
+  #[serde(alias = "friction")]
+  pub decay: f64,
+  /// Seconds a fling glides before stopping; 0 disables gliding
+  #[serde(default = "default_coast_time")]
+  pub coast_time: f64,
+  /// Wheel notches per second needed on release to glide
+  #[serde(default = "default_scroll_fling_speed")]
+  pub fling_speed: f64,
+}
+
+fn default_initial_speed() -> f64 {
+  150.0
+}
+
+fn default_decay() -> f64 {
+  7.5
+}
+
+fn default_scroll_initial_speed() -> f64 {
+  4.0
+}
+
+fn default_ramp_time() -> f64 {
+  0.8
+}
+
+fn default_speed_scale() -> f64 {
+  1.0
+}
+
+fn default_coast_time() -> f64 {
+  0.4
+}
+
+fn default_fling_speed() -> f64 {
+  1200.0
+}
+
+fn default_scroll_fling_speed() -> f64 {
+  15.0
+}
+
+fn default_reversal_brake() -> f64 {
+  40.0
+}
+
+fn default_reversal_mode() -> String {
+  "precise".to_string()
+}
+
+fn default_reversal_time() -> f64 {
+  0.3
+}
+
+impl Default for ScrollConfig {
+  fn default() -> Self {
+    Self {
+      curve_type: CurveType::Linear,
+      max_speed: 25.0,
+      initial_speed: default_scroll_initial_speed(),
+      ramp_time: default_ramp_time(),
+      acceleration: 0.5,
+      decay: 15.0,
+      coast_time: default_coast_time(),
+      fling_speed: default_scroll_fling_speed(),
+    }
+  }
+}
+
+impl ScrollConfig {
+  /// Motion settings for the scroll engine, sharing the frame rate and reversal policy of `base`
+  pub fn to_motion(&self, base: &MotionConfig) -> MotionConfig {
+    MotionConfig {
+      curve_type: self.curve_type.clone(),
+      max_speed: self.max_speed,
+      initial_speed: self.initial_speed,
+      ramp_time: self.ramp_time,
+      acceleration: self.acceleration,
+      decay: self.decay,
+      coast_time: self.coast_time,
+      fling_speed: self.fling_speed,
+      // Pixel limits and screen proportions make no sense for the wheel
+      max_acceleration: None,
+      max_jerk: None,
+      speed_scale_x: default_speed_scale(),
+      speed_scale_y: default_speed_scale(),
+      max_speed_x: None,
+      max_speed_y: None,
+      ..base.clone()
+    }
+  }
+}
+
+/// Each binding is a key with optional modifiers, e.g. "w", "shift+Left" or "ctrl+alt+k",
+/// or an array of them when several keys should trigger the same action
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyBindings {
-  pub move_up: String,
-  pub move_down: String,
-  pub move_left: String,
-  pub move_right: String,
-  pub mode_normal: String,
-  pub mode_precise: String,
-  pub mode_fast: String,
-  pub toggle_active: String,
-  pub emergency_stop: String,
+  pub move_up: Binding,
+  pub move_down: Binding,
+  pub move_left: Binding,
+  pub move_right: Binding,
+  /// Single-key diagonals
+  #[serde(default)]
+  pub move_ne: Binding,
+  #[serde(default)]
+  pub move_nw: Binding,
+  #[serde(default)]
+  pub move_se: Binding,
+  #[serde(default)]
+  pub move_sw: Binding,
+  /// Keys that move at any other angle
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub move_angles: Vec<AngleBinding>,
+  /// Deprecated: bind modes with `key` in their `[modes.<name>]` table instead
+  #[serde(default, skip_serializing_if = "Binding::is_empty")]
+  pub mode_normal: Binding,
+  /// Deprecated, see `mode_normal`
+  #[serde(default, skip_serializing_if = "Binding::is_empty")]
+  pub mode_precise: Binding,
+  /// Deprecated, see `mode_normal`
+  #[serde(default, skip_serializing_if = "Binding::is_empty")]
+  pub mode_fast: Binding,
+  /// Mouse buttons are held for as long as their key is
+  #[serde(default = "default_click_left")]
+  pub click_left: Binding,
+  #[serde(default = "default_click_middle")]
+  pub click_middle: Binding,
+  #[serde(default = "default_click_right")]
+  pub click_right: Binding,
+  #[serde(default)]
+  pub click_back: Binding,
+  #[serde(default)]
+  pub click_forward: Binding,
+  /// Latches the left button down until pressed again, for click-and-drag
+  #[serde(default = "default_drag_lock")]
+  pub drag_lock: Binding,
+  /// Switches the direction keys between moving the pointer and scrolling
+  #[serde(default = "default_toggle_scroll")]
+  pub toggle_scroll: Binding,
+  /// Stops a glide (or any leftover motion) on the spot
+  #[serde(default = "default_cancel_glide")]
+  pub cancel_glide: Binding,
+  pub toggle_active: Binding,
+  pub emergency_stop: Binding,
 }
 
+/// A key that moves the pointer at a fixed angle
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ModeConfig {
-  pub normal_multiplier: f64,
-  pub precise_multiplier: f64,
-  pub fast_multiplier: f64,
+pub struct AngleBinding {
+  /// Compass bearing in degrees: 0 is up, 90 is right, 180 is down, 270 is left
+  pub angle: u16,
+  pub key: Binding,
+}
+
+fn default_click_left() -> Binding {
+  KeyCombo::new("j").into()
+}
+
+fn default_click_middle() -> Binding {
+  KeyCombo::new("k").into()
+}
+
+fn default_click_right() -> Binding {
+  KeyCombo::new("l").into()
+}
+
+fn default_drag_lock() -> Binding {
+  KeyCombo::new("semicolon").into()
+}
+
+fn default_toggle_scroll() -> Binding {
+  KeyCombo::new("e").into()
+}
+
+fn default_cancel_glide() -> Binding {
+  KeyCombo::new("q").into()
+}
+
+/// Name of the mode kact starts in
+pub const DEFAULT_MODE: &str = "normal";
+
+/// A named speed mode, `[modes.<name>]`, overriding parts of `[motion]`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModeDefinition {
+  /// Speed multiplier applied on top of the mode's `max_speed`
+  #[serde(default = "default_multiplier")]
+  pub multiplier: f64,
+  /// Key that switches to this mode
+  #[serde(default, skip_serializing_if = "Binding::is_empty")]
+  pub key: Binding,
+  /// Only stay in this mode while the key is held, then return to the previous one
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub hold: bool,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub curve_type: Option<CurveType>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_speed: Option<f64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub decay: Option<f64>,
+  /// Deprecated per-frame factor, as in `MotionConfig`
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub friction: Option<f64>,
+}
+
+fn default_multiplier() -> f64 {
+  1.0
+}
+
+impl Default for ModeDefinition {
+  fn default() -> Self {
+    Self {
+      multiplier: default_multiplier(),
+      key: Binding::default(),
+      hold: false,
+      curve_type: None,
+      max_speed: None,
+      decay: None,
+      friction: None,
+    }
+  }
+}
+
+impl ModeDefinition {
+  pub fn new(multiplier: f64, key: &'static str) -> Self {
+    Self {
+      multiplier,
+      key: KeyCombo::new(key).into(),
+      ..Self::default()
+    }
+  }
+
+  /// `base` with this mode's overrides applied
+  pub fn apply(&self, base: &MotionConfig) -> MotionConfig {
+    MotionConfig {
+      curve_type: self.curve_type.clone().unwrap_or_else(|| base.curve_type.clone()),
+      max_speed: self.max_speed.unwrap_or(base.max_speed),
+      decay: self.decay.unwrap_or(base.decay),
+      ..base.clone()
+    }
+  }
+}
+
+/// All modes by name, the `[modes]` table.
+///
+/// Also reads the old flat layout (`normal_multiplier = 1.0`, ...), mapping each
+/// `<name>_multiplier` onto the multiplier of mode `<name>`.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(transparent)]
+pub struct ModeConfig(pub BTreeMap<String, ModeDefinition>);
+
+impl ModeConfig {
+  pub fn get(&self, name: &str) -> Option<&ModeDefinition> {
+    self.0.get(name)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&String, &ModeDefinition)> {
+    self.0.iter()
+  }
+
+  /// The same modes with only their multipliers, for engines whose units differ from `[motion]`
+  pub fn multipliers_only(&self) -> Self {
+    Self(
+      self
+        .0
+        .iter()
+        .map(|(name, mode)| {
+          (
+            name.clone(),
+            ModeDefinition {
+              multiplier: mode.multiplier,
+              ..ModeDefinition::default()
+            },
+          )
+        })
+        .collect(),
+    )
+  }
+}
+
+impl<'de> Deserialize<'de> for ModeConfig {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+    struct ModeConfigVisitor;
+
+    impl<'de> Visitor<'de> for ModeConfigVisitor {
+      type Value = ModeConfig;
+
+      fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a table of modes")
+      }
+
+      fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<ModeConfig, A::Error> {
+        let legacy = |multiplier| ModeDefinition {
+          multiplier,
+          ..ModeDefinition::default()
+        };
+        let mut modes = BTreeMap::new();
+        while let Some(key) = map.next_key::<String>()? {
+          // A number under `<name>_multiplier` is the old layout; a table always defines
+          // a mode of its own name, so `[modes.turbo_multiplier]` is a mode too
+          let (name, mode) = match (map.next_value::<toml::Value>()?, key.strip_suffix("_multiplier")) {
+            (toml::Value::Float(multiplier), Some(name)) => (name.to_string(), legacy(multiplier)),
+            (toml::Value::Integer(multiplier), Some(name)) => (name.to_string(), legacy(multiplier as f64)),
+            (value, _) => (
+              key.clone(),
+              ModeDefinition::deserialize(value)
+                .map_err(|e| de::Error::custom(format!("modes.{}: {}", key, e.message())))?,
+            ),
+          };
+          // TOML keys are unique, so a mode seen twice was given in both layouts
+          if modes.contains_key(&name) {
+            return Err(de::Error::custom(format!(
+              "mode '{0}' is given both as {0}_multiplier and as [modes.{0}]; keep only the table",
+              name
+            )));
+          }
+          modes.insert(name, mode);
+        }
+        Ok(ModeConfig(modes))
+      }
+    }
+
+    deserializer.deserialize_map(ModeConfigVisitor)
+  }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,37 +449,95 @@ pub struct SystemConfig {
   pub hot_reload: bool,
   /// Log level: "error", "warn", "info", "debug", "trace"
   pub log_level: String,
+  /// Release the keyboard grab when the motion thread stops responding for this long
+  #[serde(default = "default_grab_timeout_ms")]
+  pub grab_timeout_ms: u64,
+  /// Which platform backend to use; read once at startup
+  #[serde(default)]
+  pub backend: Backend,
+}
+
+/// Platform backend selection on Linux
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+  /// evdev and uinput, falling back to X11 when they are not accessible
+  #[default]
+  Auto,
+  /// evdev keyboards and a uinput pointer only
+  Uinput,
+  /// XInput2 and XTest only, e.g. under Xvfb
+  X11,
+}
+
+fn default_grab_timeout_ms() -> u64 {
+  500
 }
 
 impl Default for Config {
   fn default() -> Self {
     Self {
       motion: MotionConfig {
-        curve_type: "sigmoid".to_string(),
+        curve_type: CurveType::Sigmoid,
         max_speed: 2000.0,
+        initial_speed: default_initial_speed(),
+        ramp_time: default_ramp_time(),
+        speed_scale_x: default_speed_scale(),
+        speed_scale_y: default_speed_scale(),
+        max_speed_x: None,
+        max_speed_y: None,
         acceleration: 0.8,
-        friction: 0.95,
+        libinput_speed: 0.0,
+        decay: default_decay(),
+        friction: None,
+        coast_time: default_coast_time(),
+        max_acceleration: None,
+        max_jerk: None,
+        fling_speed: default_fling_speed(),
+        reversal: Reversal::default(),
+        reversal_brake: default_reversal_brake(),
+        reversal_mode: default_reversal_mode(),
+        reversal_time: default_reversal_time(),
         target_fps: 144,
       },
+      scroll: ScrollConfig::default(),
       keybindings: KeyBindings {
-        move_up: "w".to_string(),
-        move_down: "s".to_string(),
-        move_left: "a".to_string(),
-        move_right: "d".to_string(),
-        mode_normal: "1".to_string(),
-        mode_precise: "2".to_string(),
-        mode_fast: "3".to_string(),
-        toggle_active: "space".to_string(),
-        emergency_stop: "escape".to_string(),
-      },
-      modes: ModeConfig {
-        normal_multiplier: 1.0,
-        precise_multiplier: 0.3,
-        fast_multiplier: 2.5,
+        move_up: KeyCombo::new("w").into(),
+        move_down: KeyCombo::new("s").into(),
+        move_left: KeyCombo::new("a").into(),
+        move_right: KeyCombo::new("d").into(),
+        move_ne: Binding::default(),
+        move_nw: Binding::default(),
+        move_se: Binding::default(),
+        move_sw: Binding::default(),
+        move_angles: Vec::new(),
+        mode_normal: Binding::default(),
+        mode_precise: Binding::default(),
+        mode_fast: Binding::default(),
+        click_left: default_click_left(),
+        click_middle: default_click_middle(),
+        click_right: default_click_right(),
+        click_back: Binding::default(),
+        click_forward: Binding::default(),
+        drag_lock: default_drag_lock(),
+        toggle_scroll: default_toggle_scroll(),
+        cancel_glide: default_cancel_glide(),
+        toggle_active: "ctrl+alt+space"
+          .parse::<KeyCombo>()
+          .expect("valid default binding")
+          .into(),
+        emergency_stop: KeyCombo::new("escape").into(),
       },
+      modes: ModeConfig(BTreeMap::from([
+        (DEFAULT_MODE.to_string(), ModeDefinition::new(1.0, "1")),
+        ("precise".to_string(), ModeDefinition::new(0.3, "2")),
+        ("fast".to_string(), ModeDefinition::new(2.5, "3")),
+      ])),
       system: SystemConfig {
         hot_reload: true,
         log_level: "info".to_string(),
+        grab_timeout_ms: default_grab_timeout_ms(),
+        backend: Backend::default(),
       },
     }
   }
@@ -90,10 +546,186 @@ impl Default for Config {
 impl Config {
   pub fn load(path: &Path) -> Result<Self> {
     let contents = fs::read_to_string(path)?;
-    let config: Config = toml::from_str(&contents)?;
+    let mut config: Config = toml::from_str(&contents)?;
+    config.migrate_mode_bindings();
+    config.migrate_friction()?;
+    config.validate()?;
     Ok(config)
   }
 
+  /// Moves the deprecated `keybindings.mode_*` entries onto the `key` of their mode
+  fn migrate_mode_bindings(&mut self) {
+    let legacy = [
+      ("normal", &mut self.keybindings.mode_normal),
+      ("precise", &mut self.keybindings.mode_precise),
+      ("fast", &mut self.keybindings.mode_fast),
+    ];
+    for (name, binding) in legacy {
+      if binding.is_empty() {
+        continue;
+      }
+      let mode = self.modes.0.entry(name.to_string()).or_default();
+      if mode.key.is_empty() {
+        mode.key = std::mem::take(binding);
+      } else {
+        tracing::warn!("Ignoring keybindings.mode_{}, modes.{}.key is set", name, name);
+        *binding = Binding::default();
+      }
+    }
+  }
+
+  /// Converts the old per-frame `friction` factor into a `decay` rate per second,
+  /// keeping the glide it produced at `target_fps`
+  fn migrate_friction(&mut self) -> Result<()> {
+    let fps = self.motion.target_fps as f64;
+    let convert = |section: &str, friction: Option<f64>, decay: &mut f64| {
+      let Some(friction) = friction else {
+        return Ok(());
+      };
+      // 1.0 kept all speed, a decay of 0; 0.0 stopped dead, which no rate reaches
+      if !(friction > 0.0 && friction <= 1.0) {
+        return Err(Error::Config(format!(
+          "{}.friction = {} must be a per-frame factor in (0.0, 1.0]; set {}.decay instead",
+          section, friction, section
+        )));
+      }
+      *decay = -fps * friction.ln();
+      tracing::warn!(
+        "{}.friction = {} is deprecated; read as decay = {:.2} per second",
+        section,
+        friction,
+        decay
+      );
+      Ok(())
+    };
+
+    convert("motion", self.motion.friction.take(), &mut self.motion.decay)?;
+    for (name, mode) in self.modes.0.iter_mut() {
+      if let Some(friction) = mode.friction.take() {
+        convert(&format!("modes.{}", name), Some(friction), mode.decay.insert(0.0))?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Rejects invalid curves and modes and conflicting key bindings, and logs the
+  /// bindings that merely shadow each other
+  pub fn validate(&self) -> Result<()> {
+    let check_curve = |section: &str, curve: &CurveType| {
+      curve
+        .build(self.motion.acceleration)
+        .map(drop)
+        .map_err(|e| Error::Config(format!("{}.curve_type: {}", section, e)))
+    };
+    check_curve("motion", &self.motion.curve_type)?;
+    for (name, limit) in [
+      ("max_acceleration", self.motion.max_acceleration),
+      ("max_jerk", self.motion.max_jerk),
+      ("max_speed_x", self.motion.max_speed_x),
+      ("max_speed_y", self.motion.max_speed_y),
+      ("speed_scale_x", Some(self.motion.speed_scale_x)),
+      ("speed_scale_y", Some(self.motion.speed_scale_y)),
+    ] {
+      if let Some(limit) = limit
+        && !(limit.is_finite() && limit > 0.0)
+      {
+        return Err(Error::Config(format!(
+          "motion.{} must be a positive number, got {}",
+          name, limit
+        )));
+      }
+    }
+    for (name, value) in [
+      ("motion.initial_speed", self.motion.initial_speed),
+      ("motion.ramp_time", self.motion.ramp_time),
+      ("motion.coast_time", self.motion.coast_time),
+      ("motion.fling_speed", self.motion.fling_speed),
+      ("motion.decay", self.motion.decay),
+      ("scroll.initial_speed", self.scroll.initial_speed),
+      ("scroll.ramp_time", self.scroll.ramp_time),
+      ("scroll.coast_time", self.scroll.coast_time),
+      ("scroll.fling_speed", self.scroll.fling_speed),
+      ("scroll.decay", self.scroll.decay),
+    ] {
+      if !(value.is_finite() && value >= 0.0) {
+        return Err(Error::Config(format!("{} must not be negative, got {}", name, value)));
+      }
+    }
+    if self.motion.target_fps == 0 {
+      return Err(Error::Config("motion.target_fps must be at least 1".to_string()));
+    }
+    if self.motion.max_jerk.is_some() && self.motion.max_acceleration.is_none() {
+      return Err(Error::Config(
+        "motion.max_jerk needs motion.max_acceleration".to_string(),
+      ));
+    }
+    if !(-1.0..=1.0).contains(&self.motion.libinput_speed) {
+      return Err(Error::Config(format!(
+        "motion.libinput_speed must be between -1.0 and 1.0, got {}",
+        self.motion.libinput_speed
+      )));
+    }
+    if self.motion.reversal == Reversal::Precise && self.modes.get(&self.motion.reversal_mode).is_none() {
+      return Err(Error::Config(format!(
+        "motion.reversal_mode names unknown mode '{}'",
+        self.motion.reversal_mode
+      )));
+    }
+    check_curve("scroll", &self.scroll.curve_type)?;
+
+    for (name, mode) in self.modes.iter() {
+      if !(mode.multiplier.is_finite() && mode.multiplier > 0.0) {
+        return Err(Error::Config(format!(
+          "modes.{}.multiplier must be a positive number, got {}",
+          name, mode.multiplier
+        )));
+      }
+      if let Some(decay) = mode.decay
+        && !(decay.is_finite() && decay >= 0.0)
+      {
+        return Err(Error::Config(format!(
+          "modes.{}.decay must not be negative, got {}",
+          name, decay
+        )));
+      }
+      if let Some(curve) = &mode.curve_type {
+        check_curve(&format!("modes.{}", name), curve)?;
+      }
+    }
+
+    for binding in &self.keybindings.move_angles {
+      if binding.angle >= 360 {
+        return Err(Error::Config(format!(
+          "keybindings.move_angles: angle {} is not a compass bearing (0-359)",
+          binding.angle
+        )));
+      }
+    }
+    for (name, binding) in [
+      ("toggle_active", &self.keybindings.toggle_active),
+      ("emergency_stop", &self.keybindings.emergency_stop),
+    ] {
+      if binding.is_empty() {
+        return Err(Error::Config(format!("keybindings.{} needs at least one key", name)));
+      }
+    }
+
+    let (errors, warnings): (Vec<_>, Vec<_>) = find_conflicts(&self.keybindings, &self.modes)
+      .into_iter()
+      .partition(|conflict| conflict.is_error());
+
+    for conflict in &warnings {
+      tracing::warn!("Key binding conflict: {}", conflict);
+    }
+
+    if !errors.is_empty() {
+      let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+      return Err(Error::Config(messages.join("; ")));
+    }
+
+    Ok(())
+  }
+
   pub fn load_or_default(path: &Path) -> Self {
     Self::load(path).unwrap_or_else(|e| {
       eprintln!("Failed to load config: {}. Using defaults.", e);
@@ -105,3 +737,105 @@ impl Config {
     PathBuf::from("kact.toml")
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn legacy_and_table_modes_cannot_be_mixed() {
+    let legacy = toml::from_str::<ModeConfig>("fast_multiplier = 9.0").unwrap();
+    assert_eq!(legacy.get("fast").unwrap().multiplier, 9.0);
+
+    for source in [
+      "fast_multiplier = 9.0\n[fast]\nmultiplier = 2.5\nkey = \"3\"",
+      "fast = { multiplier = 2.5, key = \"3\" }\nfast_multiplier = 9.0",
+    ] {
+      let error = toml::from_str::<ModeConfig>(source).unwrap_err();
+      assert!(error.to_string().contains("mode 'fast' is given both"), "{}", error);
+    }
+
+    // Only a number makes `_multiplier` the old layout; a table is a mode of that name
+    let modes =
+      toml::from_str::<ModeConfig>("fast_multiplier = 9\n[turbo_multiplier]\nmultiplier = 4.0\nkey = \"4\"").unwrap();
+    assert_eq!(modes.get("fast").unwrap().multiplier, 9.0);
+    assert_eq!(modes.get("turbo_multiplier").unwrap().multiplier, 4.0);
+    assert!(modes.get("turbo").is_none());
+  }
+
+  #[test]
+  fn per_frame_friction_becomes_an_equivalent_decay_rate() {
+    let source = include_str!("../kact.toml")
+      .replace("decay = 7.5", "friction = 0.92")
+      .replace("decay = 15.0", "friction = 0.5")
+      .replace("[modes.precise]", "[modes.precise]\nfriction = 0.5");
+    let mut config: Config = toml::from_str(&source).unwrap();
+    config.motion.target_fps = 144;
+    config.migrate_friction().unwrap();
+
+    // One frame of the new decay shrinks velocity as much as the old factor did
+    let frame = 1.0 / 144.0;
+    assert!(((-config.motion.decay * frame).exp() - 0.92).abs() < 1e-12);
+    let precise = config.modes.get("precise").unwrap().decay.unwrap();
+    assert!(((-precise * frame).exp() - 0.5).abs() < 1e-12);
+    // In [scroll] friction was always a rate and stays one
+    assert_eq!(config.scroll.decay, 0.5);
+
+    config.motion.friction = Some(0.0);
+    assert!(config.migrate_friction().is_err());
+  }
+
+  #[test]
+  fn baseline_config_loads() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("kact.toml");
+    Config::load(&path).unwrap();
+  }
+
+  #[test]
+  fn control_keys_cannot_be_unbound() {
+    let mut config = Config::default();
+    config.keybindings.toggle_active = Binding::default();
+    assert!(config.validate().is_err());
+
+    let mut config = Config::default();
+    config.keybindings.emergency_stop = Binding::default();
+    assert!(config.validate().is_err());
+  }
+
+  #[test]
+  fn negative_timings_and_zero_fps_are_rejected() {
+    let cases: [fn(&mut Config); 6] = [
+      |c| c.motion.initial_speed = -1.0,
+      |c| c.motion.ramp_time = -0.5,
+      |c| c.motion.coast_time = -1.0,
+      |c| c.motion.fling_speed = f64::NAN,
+      |c| c.scroll.coast_time = -1.0,
+      |c| c.motion.target_fps = 0,
+    ];
+    for (i, break_config) in cases.iter().enumerate() {
+      let mut config = Config::default();
+      break_config(&mut config);
+      assert!(config.validate().is_err(), "case {} was accepted", i);
+    }
+
+    // Zero is meaningful: no gliding, no ramp, start from rest
+    let mut config = Config::default();
+    config.motion.coast_time = 0.0;
+    config.motion.ramp_time = 0.0;
+    config.motion.initial_speed = 0.0;
+    config.validate().unwrap();
+  }
+
+  #[test]
+  fn angles_must_be_compass_bearings() {
+    let mut config = Config::default();
+    config.keybindings.move_angles = vec![AngleBinding {
+      angle: 359,
+      key: KeyCombo::new("u").into(),
+    }];
+    config.validate().unwrap();
+
+    config.keybindings.move_angles[0].angle = 360;
+    assert!(config.validate().is_err());
+  }
+}
@@ -1,7 +1,15 @@
+pub mod accel;
+pub mod curve;
+pub mod keymap;
+pub mod keys;
 pub mod motion;
 pub mod state;
 pub mod types;
 
+pub use accel::AccelProfile;
+pub use curve::{Curve, CurveType};
+pub use keymap::{InputEvent, KeyEvent, KeyMapper, KeyRoute};
+pub use keys::{Binding, KeyCombo, Modifiers};
 pub use motion::MotionEngine;
-pub use state::{AppState, InputState, Mode};
-pub use types::{Direction, Vector2D};
+pub use state::{AppState, Glide, InputState, Mode, MotionState};
+pub use types::{Direction, MouseButton, Vector2D};
@@ -0,0 +1,119 @@
+use std::fmt;
+
+/// Velocity-dependent gain applied on top of the unaccelerated pointer speed.
+///
+/// Velocities are in units per millisecond, where libinput's units are those of a
+/// normalized 1000 DPI mouse; kact treats one unit as one pixel.
+pub trait AccelProfile: Send + Sync + fmt::Debug {
+  /// Acceleration factor for a pointer moving at `velocity` units/ms
+  fn factor(&self, velocity: f64) -> f64;
+}
+
+/// libinput's "adaptive" profile (`pointer_accel_profile_linear`).
+///
+/// Slow motion is decelerated for precision, a plateau around the threshold
+/// moves 1:1, and faster motion is accelerated along a line up to a maximum.
+#[derive(Debug, Clone, Copy)]
+pub struct AdaptiveProfile {
+  /// Velocity above which acceleration kicks in, in units/ms
+  threshold: f64,
+  /// Maximum acceleration factor
+  max_accel: f64,
+  /// Slope of the factor above the threshold
+  incline: f64,
+}
+
+impl AdaptiveProfile {
+  /// Velocity below which motion is decelerated, in units/ms
+  const DECEL_THRESHOLD: f64 = 0.07;
+
+  /// Profile for libinput's speed setting in [-1, 1]
+  pub fn new(speed: f64) -> Self {
+    Self {
+      threshold: (0.4 - 0.25 * speed).max(0.2),
+      max_accel: 2.0 + 1.5 * speed,
+      incline: 1.1 + 0.75 * speed,
+    }
+  }
+}
+
+impl AccelProfile for AdaptiveProfile {
+  fn factor(&self, velocity: f64) -> f64 {
+    let factor = if velocity < Self::DECEL_THRESHOLD {
+      10.0 * velocity + 0.3
+    } else if velocity < self.threshold {
+      1.0
+    } else {
+      self.incline * (velocity - self.threshold) + 1.0
+    };
+    factor.min(self.max_accel)
+  }
+}
+
+/// libinput's "flat" profile: a constant factor set by the speed setting
+#[derive(Debug, Clone, Copy)]
+pub struct FlatProfile {
+  factor: f64,
+}
+
+impl FlatProfile {
+  /// Profile for libinput's speed setting in [-1, 1]
+  pub fn new(speed: f64) -> Self {
+    Self {
+      factor: (1.0 + speed).max(0.005),
+    }
+  }
+}
+
+impl AccelProfile for FlatProfile {
+  fn factor(&self, _velocity: f64) -> f64 {
+    self.factor
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(actual: f64, expected: f64) {
+    assert!(
+      (actual - expected).abs() < 1e-9,
+      "expected {}, got {}",
+      expected,
+      actual
+    );
+  }
+
+  /// Reference values from libinput's `pointer_accel_profile_linear`, worked out by hand
+  #[test]
+  fn adaptive_matches_libinput() {
+    // speed 0: threshold 0.4, max_accel 2.0, incline 1.1
+    let profile = AdaptiveProfile::new(0.0);
+    assert_close(profile.factor(0.05), 0.8);
+    assert_close(profile.factor(0.3), 1.0);
+    assert_close(profile.factor(1.0), 1.66);
+    assert_close(profile.factor(5.0), 2.0);
+
+    // speed -1: threshold 0.65, max_accel 0.5, incline 0.35; the cap covers all but the slowest motion
+    let profile = AdaptiveProfile::new(-1.0);
+    assert_close(profile.factor(0.01), 0.4);
+    assert_close(profile.factor(0.5), 0.5);
+    assert_close(profile.factor(1.0), 0.5);
+    assert_close(profile.factor(5.0), 0.5);
+
+    // speed 1: threshold floored at 0.2, max_accel 3.5, incline 1.85
+    let profile = AdaptiveProfile::new(1.0);
+    assert_close(profile.factor(0.05), 0.8);
+    assert_close(profile.factor(0.1), 1.0);
+    assert_close(profile.factor(1.0), 2.48);
+    assert_close(profile.factor(3.0), 3.5);
+  }
+
+  #[test]
+  fn flat_matches_libinput() {
+    assert_close(FlatProfile::new(-1.0).factor(1.0), 0.005);
+    assert_close(FlatProfile::new(-0.5).factor(1.0), 0.5);
+    assert_close(FlatProfile::new(0.0).factor(0.01), 1.0);
+    assert_close(FlatProfile::new(1.0).factor(5.0), 2.0);
+  }
+}
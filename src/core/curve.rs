@@ -0,0 +1,221 @@
+use super::accel::{AccelProfile, AdaptiveProfile, FlatProfile};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Acceleration curve mapping ramp progress in [0, 1] onto a speed fraction in [0, 1]
+pub trait Curve: Send + Sync + fmt::Debug {
+  fn apply(&self, x: f64) -> f64;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Linear;
+
+impl Curve for Linear {
+  fn apply(&self, x: f64) -> f64 {
+    x
+  }
+}
+
+/// Logistic S-curve, rescaled so that 0 maps to 0 and 1 to 1
+#[derive(Debug, Clone, Copy)]
+pub struct Sigmoid {
+  steepness: f64,
+}
+
+impl Sigmoid {
+  pub fn new(steepness: f64) -> Self {
+    Self { steepness }
+  }
+
+  fn raw(&self, x: f64) -> f64 {
+    1.0 / (1.0 + (-self.steepness * (x - 0.5)).exp())
+  }
+}
+
+impl Curve for Sigmoid {
+  fn apply(&self, x: f64) -> f64 {
+    if self.steepness <= 0.0 {
+      return x;
+    }
+    let (low, high) = (self.raw(0.0), self.raw(1.0));
+    (self.raw(x) - low) / (high - low)
+  }
+}
+
+/// x^p, where p controls how long the curve stays flat
+#[derive(Debug, Clone, Copy)]
+pub struct Exponential {
+  power: f64,
+}
+
+impl Exponential {
+  pub fn new(power: f64) -> Self {
+    Self { power }
+  }
+}
+
+impl Curve for Exponential {
+  fn apply(&self, x: f64) -> f64 {
+    x.powf(self.power)
+  }
+}
+
+/// Straight lines between user-given points, flat beyond the first and last
+#[derive(Debug, Clone)]
+pub struct PiecewiseLinear {
+  points: Vec<[f64; 2]>,
+}
+
+impl PiecewiseLinear {
+  /// Points must have strictly increasing x, with both coordinates in [0, 1]
+  pub fn new(points: Vec<[f64; 2]>) -> Result<Self, String> {
+    if points.len() < 2 {
+      return Err("a piecewise curve needs at least two points".to_string());
+    }
+    if let Some([x, y]) = points
+      .iter()
+      .find(|[x, y]| !(0.0..=1.0).contains(x) || !(0.0..=1.0).contains(y))
+    {
+      return Err(format!("piecewise point [{}, {}] is outside [0, 1]", x, y));
+    }
+    if let Some(pair) = points.windows(2).find(|pair| pair[0][0] >= pair[1][0]) {
+      return Err(format!(
+        "piecewise points must have increasing x, but {} is followed by {}",
+        pair[0][0], pair[1][0]
+      ));
+    }
+    Ok(Self { points })
+  }
+}
+
+impl Curve for PiecewiseLinear {
+  fn apply(&self, x: f64) -> f64 {
+    let (first, last) = (self.points[0], self.points[self.points.len() - 1]);
+    if x <= first[0] {
+      return first[1];
+    }
+    if x >= last[0] {
+      return last[1];
+    }
+
+    let i = self.points.partition_point(|[px, _]| *px <= x);
+    let ([x0, y0], [x1, y1]) = (self.points[i - 1], self.points[i]);
+    y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+  }
+}
+
+/// Cubic Bézier from (0, 0) to (1, 1) with two control points, as in CSS `cubic-bezier()`
+#[derive(Debug, Clone, Copy)]
+pub struct CubicBezier {
+  p1: [f64; 2],
+  p2: [f64; 2],
+}
+
+impl CubicBezier {
+  /// Control points must lie in [0, 1]: x so the curve is a function of x, and y
+  /// so the speed fraction never leaves [0, 1]
+  pub fn new([x1, y1, x2, y2]: [f64; 4]) -> Result<Self, String> {
+    if !(0.0..=1.0).contains(&x1) || !(0.0..=1.0).contains(&x2) {
+      return Err(format!(
+        "bezier control point x values must be in [0, 1], got {} and {}",
+        x1, x2
+      ));
+    }
+    if !(0.0..=1.0).contains(&y1) || !(0.0..=1.0).contains(&y2) {
+      return Err(format!(
+        "bezier control point y values must be in [0, 1], got {} and {}",
+        y1, y2
+      ));
+    }
+    Ok(Self {
+      p1: [x1, y1],
+      p2: [x2, y2],
+    })
+  }
+
+  /// One coordinate of the curve at parameter t
+  fn at(t: f64, c1: f64, c2: f64) -> f64 {
+    let u = 1.0 - t;
+    3.0 * u * u * t * c1 + 3.0 * u * t * t * c2 + t * t * t
+  }
+}
+
+impl Curve for CubicBezier {
+  fn apply(&self, x: f64) -> f64 {
+    let x = x.clamp(0.0, 1.0);
+
+    // x(t) is monotonic for control points in [0, 1], so bisection always converges
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..32 {
+      let mid = (low + high) / 2.0;
+      if Self::at(mid, self.p1[0], self.p2[0]) < x {
+        low = mid;
+      } else {
+        high = mid;
+      }
+    }
+
+    Self::at((low + high) / 2.0, self.p1[1], self.p2[1])
+  }
+}
+
+/// Curve selection as written in the config.
+///
+/// Either a name, `curve_type = "sigmoid"`, or a table with the curve's points:
+/// `curve_type = { piecewise = [[0.0, 0.0], [0.5, 0.2], [1.0, 1.0]] }` or
+/// `curve_type = { bezier = [0.6, 0.0, 0.9, 0.4] }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurveType {
+  Sigmoid,
+  Exponential,
+  Linear,
+  Piecewise(Vec<[f64; 2]>),
+  Bezier([f64; 4]),
+  /// Ramps linearly, then applies libinput's adaptive acceleration
+  #[serde(rename = "libinput-adaptive")]
+  LibinputAdaptive,
+  /// Ramps linearly, then applies libinput's flat acceleration
+  #[serde(rename = "libinput-flat")]
+  LibinputFlat,
+}
+
+impl CurveType {
+  /// Builds the curve; `acceleration` sets the steepness of the sigmoid and exponential curves
+  pub fn build(&self, acceleration: f64) -> Result<Box<dyn Curve>, String> {
+    Ok(match self {
+      CurveType::Sigmoid => Box::new(Sigmoid::new(10.0 * acceleration)),
+      CurveType::Exponential => Box::new(Exponential::new(1.0 + (1.0 - acceleration) * 2.0)),
+      CurveType::Linear | CurveType::LibinputAdaptive | CurveType::LibinputFlat => Box::new(Linear),
+      CurveType::Piecewise(points) => Box::new(PiecewiseLinear::new(points.clone())?),
+      CurveType::Bezier(control) => Box::new(CubicBezier::new(*control)?),
+    })
+  }
+
+  /// The velocity-dependent acceleration to apply after the curve, for libinput's
+  /// speed setting `speed` in [-1, 1]
+  pub fn accel_profile(&self, speed: f64) -> Option<Box<dyn AccelProfile>> {
+    match self {
+      CurveType::LibinputAdaptive => Some(Box::new(AdaptiveProfile::new(speed))),
+      CurveType::LibinputFlat => Some(Box::new(FlatProfile::new(speed))),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn bezier_rejects_control_points_outside_unit_square() {
+    assert!(CubicBezier::new([0.5, -1.0, 0.5, 2.0]).is_err());
+    assert!(CubicBezier::new([1.5, 0.0, 0.5, 1.0]).is_err());
+
+    let curve = CubicBezier::new([0.6, 0.0, 0.9, 0.4]).unwrap();
+    for i in 0..=100 {
+      let y = curve.apply(i as f64 / 100.0);
+      assert!((0.0..=1.0).contains(&y), "{} at {}", y, i);
+    }
+  }
+}
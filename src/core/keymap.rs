@@ -0,0 +1,464 @@
+use super::keys::{Binding, KeyCombo, Modifiers};
+use super::state::Mode;
+use super::types::{Direction, MouseButton};
+use crate::config::{KeyBindings, ModeConfig};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Raw key event as reported by an input backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyEvent {
+  /// Lowercase key name, e.g. "w", "space", "kp_4"
+  pub key: String,
+  /// Backend-specific key code, used to re-emit the key
+  pub code: u32,
+  pub pressed: bool,
+  /// Modifiers held when the event occurred
+  pub modifiers: Modifiers,
+}
+
+/// Semantic input event produced by the `KeyMapper`
+#[derive(Debug, Clone)]
+pub enum InputEvent {
+  DirectionPressed(Direction),
+  DirectionReleased(Direction),
+  ModeChanged(Mode),
+  ModeHeld(Mode),
+  ModeReleased(Mode),
+  ButtonPressed(MouseButton),
+  ButtonReleased(MouseButton),
+  DragLockToggled,
+  ScrollModeToggled,
+  GlideCancelled,
+  ToggleActive,
+  EmergencyStop,
+}
+
+/// Something a key can be bound to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+  Move(Direction),
+  Mode(Mode),
+  /// A mode that lasts only while its key is held
+  HoldMode(Mode),
+  Button(MouseButton),
+  DragLock,
+  ToggleScroll,
+  CancelGlide,
+  ToggleActive,
+  EmergencyStop,
+}
+
+impl Action {
+  fn press_event(&self) -> InputEvent {
+    match self {
+      Action::Move(dir) => InputEvent::DirectionPressed(*dir),
+      Action::Mode(mode) => InputEvent::ModeChanged(mode.clone()),
+      Action::HoldMode(mode) => InputEvent::ModeHeld(mode.clone()),
+      Action::Button(button) => InputEvent::ButtonPressed(*button),
+      Action::DragLock => InputEvent::DragLockToggled,
+      Action::ToggleScroll => InputEvent::ScrollModeToggled,
+      Action::CancelGlide => InputEvent::GlideCancelled,
+      Action::ToggleActive => InputEvent::ToggleActive,
+      Action::EmergencyStop => InputEvent::EmergencyStop,
+    }
+  }
+
+  fn release_event(&self) -> Option<InputEvent> {
+    match self {
+      Action::Move(dir) => Some(InputEvent::DirectionReleased(*dir)),
+      Action::HoldMode(mode) => Some(InputEvent::ModeReleased(mode.clone())),
+      Action::Button(button) => Some(InputEvent::ButtonReleased(*button)),
+      _ => None,
+    }
+  }
+}
+
+impl fmt::Display for Action {
+  /// Formats as the name of the config entry the action is bound through
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let name = match self {
+      Action::Mode(mode) | Action::HoldMode(mode) => return write!(f, "modes.{}.key", mode),
+      Action::Move(Direction::Up) => "move_up",
+      Action::Move(Direction::Down) => "move_down",
+      Action::Move(Direction::Left) => "move_left",
+      Action::Move(Direction::Right) => "move_right",
+      Action::Move(Direction::UpRight) => "move_ne",
+      Action::Move(Direction::UpLeft) => "move_nw",
+      Action::Move(Direction::DownRight) => "move_se",
+      Action::Move(Direction::DownLeft) => "move_sw",
+      Action::Move(Direction::Custom(angle)) => return write!(f, "move_angles ({}°)", angle),
+      Action::Button(MouseButton::Left) => "click_left",
+      Action::Button(MouseButton::Middle) => "click_middle",
+      Action::Button(MouseButton::Right) => "click_right",
+      Action::Button(MouseButton::Back) => "click_back",
+      Action::Button(MouseButton::Forward) => "click_forward",
+      Action::DragLock => "drag_lock",
+      Action::ToggleScroll => "toggle_scroll",
+      Action::CancelGlide => "cancel_glide",
+      Action::ToggleActive => "toggle_active",
+      Action::EmergencyStop => "emergency_stop",
+    };
+    f.write_str(name)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+  /// The same chord is bound to two actions; only the first would ever fire
+  Duplicate,
+  /// The first chord's key is a modifier of the second chord, so pressing the
+  /// second chord triggers the first action on the way
+  Shadowed,
+}
+
+/// Two bindings that interfere with each other
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingConflict {
+  pub kind: ConflictKind,
+  pub first: (Action, KeyCombo),
+  pub second: (Action, KeyCombo),
+}
+
+impl BindingConflict {
+  /// Duplicates make a binding unreachable and are rejected; shadowing only warrants a warning
+  pub fn is_error(&self) -> bool {
+    self.kind == ConflictKind::Duplicate
+  }
+}
+
+impl fmt::Display for BindingConflict {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let (first_action, first_combo) = &self.first;
+    let (second_action, second_combo) = &self.second;
+    match self.kind {
+      ConflictKind::Duplicate => write!(
+        f,
+        "'{}' is bound to both {} and {}",
+        first_combo, first_action, second_action
+      ),
+      ConflictKind::Shadowed => write!(
+        f,
+        "'{}' ({}) shadows '{}' ({})",
+        first_combo, first_action, second_combo, second_action
+      ),
+    }
+  }
+}
+
+/// Lists the action behind each `KeyBindings` field and mode key
+fn binding_table<'a>(bindings: &'a KeyBindings, modes: &'a ModeConfig) -> Vec<(&'a Binding, Action)> {
+  let mut table = vec![
+    (&bindings.move_up, Action::Move(Direction::Up)),
+    (&bindings.move_down, Action::Move(Direction::Down)),
+    (&bindings.move_left, Action::Move(Direction::Left)),
+    (&bindings.move_right, Action::Move(Direction::Right)),
+    (&bindings.move_ne, Action::Move(Direction::UpRight)),
+    (&bindings.move_nw, Action::Move(Direction::UpLeft)),
+    (&bindings.move_se, Action::Move(Direction::DownRight)),
+    (&bindings.move_sw, Action::Move(Direction::DownLeft)),
+    (&bindings.click_left, Action::Button(MouseButton::Left)),
+    (&bindings.click_middle, Action::Button(MouseButton::Middle)),
+    (&bindings.click_right, Action::Button(MouseButton::Right)),
+    (&bindings.click_back, Action::Button(MouseButton::Back)),
+    (&bindings.click_forward, Action::Button(MouseButton::Forward)),
+    (&bindings.drag_lock, Action::DragLock),
+    (&bindings.toggle_scroll, Action::ToggleScroll),
+    (&bindings.cancel_glide, Action::CancelGlide),
+    (&bindings.toggle_active, Action::ToggleActive),
+    (&bindings.emergency_stop, Action::EmergencyStop),
+  ];
+  table.extend(
+    bindings
+      .move_angles
+      .iter()
+      .map(|angled| (&angled.key, Action::Move(Direction::Custom(angled.angle)))),
+  );
+  table.extend(modes.iter().map(|(name, mode)| {
+    let name = Mode::new(name.as_str());
+    let action = if mode.hold {
+      Action::HoldMode(name)
+    } else {
+      Action::Mode(name)
+    };
+    (&mode.key, action)
+  }));
+  table
+}
+
+fn flatten(bindings: &KeyBindings, modes: &ModeConfig) -> Vec<(KeyCombo, Action)> {
+  binding_table(bindings, modes)
+    .into_iter()
+    .flat_map(|(binding, action)| {
+      binding
+        .combos()
+        .iter()
+        .map(move |combo| (combo.clone(), action.clone()))
+    })
+    .collect()
+}
+
+/// Finds bindings that collide with or shadow one another
+pub fn find_conflicts(bindings: &KeyBindings, modes: &ModeConfig) -> Vec<BindingConflict> {
+  let combos = flatten(bindings, modes);
+  let mut conflicts = Vec::new();
+
+  for (i, (combo_a, action_a)) in combos.iter().enumerate() {
+    for (combo_b, action_b) in &combos[i + 1..] {
+      if action_a == action_b {
+        continue;
+      }
+
+      if combo_a == combo_b {
+        conflicts.push(BindingConflict {
+          kind: ConflictKind::Duplicate,
+          first: (action_a.clone(), combo_a.clone()),
+          second: (action_b.clone(), combo_b.clone()),
+        });
+        continue;
+      }
+
+      for ((shadow_action, shadow), (action, combo)) in [
+        ((action_a, combo_a), (action_b, combo_b)),
+        ((action_b, combo_b), (action_a, combo_a)),
+      ] {
+        let produced = Modifiers::of_key(shadow.key);
+        if !produced.is_empty() && combo.modifiers.contains(produced) && combo.modifiers.contains(shadow.modifiers) {
+          conflicts.push(BindingConflict {
+            kind: ConflictKind::Shadowed,
+            first: ((*shadow_action).clone(), shadow.clone()),
+            second: ((*action).clone(), combo.clone()),
+          });
+        }
+      }
+    }
+  }
+
+  conflicts
+}
+
+/// What to do with a raw key event
+#[derive(Debug, Clone)]
+pub enum KeyRoute {
+  /// The key belongs to kact and may produce an event
+  Consumed(Option<InputEvent>),
+  /// The key is not kact's and should reach the focused application
+  PassThrough,
+}
+
+/// Translates raw key events into `InputEvent`s according to `KeyBindings`
+#[derive(Debug, Clone)]
+pub struct KeyMapper {
+  bindings: Vec<(KeyCombo, Action)>,
+  /// Keys currently held down, with the action their press triggered
+  held: HashMap<String, Action>,
+}
+
+impl KeyMapper {
+  pub fn new(bindings: &KeyBindings, modes: &ModeConfig) -> Self {
+    let bindings = flatten(bindings, modes);
+
+    Self {
+      bindings,
+      held: HashMap::new(),
+    }
+  }
+
+  /// Returns the action bound to a key and exact set of modifiers, if any
+  pub fn lookup(&self, key: &str, modifiers: Modifiers) -> Option<&Action> {
+    self
+      .bindings
+      .iter()
+      .find(|(combo, _)| combo.matches(key, modifiers))
+      .map(|(_, action)| action)
+  }
+
+  /// Routes a raw key event, mapping it to the event it triggers.
+  ///
+  /// While inactive only the toggle is claimed, every other key passes through.
+  /// Releases are resolved against the action the matching press triggered, so a
+  /// key released after the bindings changed still ends what it started.
+  pub fn route(&mut self, event: &KeyEvent, active: bool) -> KeyRoute {
+    if event.pressed {
+      match self.lookup(&event.key, event.modifiers) {
+        Some(action) if active || *action == Action::ToggleActive => {
+          let action = action.clone();
+          let event_out = action.press_event();
+          self.held.insert(event.key.clone(), action);
+          KeyRoute::Consumed(Some(event_out))
+        }
+        _ => KeyRoute::PassThrough,
+      }
+    } else {
+      match self.held.remove(&event.key) {
+        Some(action) => KeyRoute::Consumed(action.release_event()),
+        None => KeyRoute::PassThrough,
+      }
+    }
+  }
+
+  /// Swaps in new bindings while keeping track of keys that are still held
+  pub fn update_bindings(&mut self, bindings: &KeyBindings, modes: &ModeConfig) {
+    let held = std::mem::take(&mut self.held);
+    *self = Self::new(bindings, modes);
+    self.held = held;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::Config;
+  use crate::core::keys::ModifierTracker;
+
+  /// Produces key events the way a backend would, tracking modifiers
+  #[derive(Default)]
+  struct Keyboard {
+    modifiers: ModifierTracker,
+  }
+
+  impl Keyboard {
+    fn key(&mut self, key: &str, pressed: bool) -> KeyEvent {
+      KeyEvent {
+        key: key.to_string(),
+        code: 0,
+        pressed,
+        modifiers: self.modifiers.update(key, pressed),
+      }
+    }
+  }
+
+  fn mapper() -> KeyMapper {
+    let config = Config::default();
+    KeyMapper::new(&config.keybindings, &config.modes)
+  }
+
+  #[test]
+  fn press_and_release_are_paired() {
+    let (mut mapper, mut keyboard) = (mapper(), Keyboard::default());
+
+    let press = mapper.route(&keyboard.key("w", true), true);
+    assert!(matches!(
+      press,
+      KeyRoute::Consumed(Some(InputEvent::DirectionPressed(Direction::Up)))
+    ));
+    let release = mapper.route(&keyboard.key("w", false), true);
+    assert!(matches!(
+      release,
+      KeyRoute::Consumed(Some(InputEvent::DirectionReleased(Direction::Up)))
+    ));
+  }
+
+  #[test]
+  fn inactive_keys_pass_through() {
+    let (mut mapper, mut keyboard) = (mapper(), Keyboard::default());
+
+    assert!(matches!(
+      mapper.route(&keyboard.key("w", true), false),
+      KeyRoute::PassThrough
+    ));
+    assert!(matches!(
+      mapper.route(&keyboard.key("w", false), false),
+      KeyRoute::PassThrough
+    ));
+    assert!(matches!(
+      mapper.route(&keyboard.key("x", true), true),
+      KeyRoute::PassThrough
+    ));
+  }
+
+  #[test]
+  fn toggle_is_consumed_while_inactive() {
+    let (mut mapper, mut keyboard) = (mapper(), Keyboard::default());
+
+    for modifier in ["control_l", "alt_l"] {
+      assert!(matches!(
+        mapper.route(&keyboard.key(modifier, true), false),
+        KeyRoute::PassThrough
+      ));
+    }
+    let press = mapper.route(&keyboard.key("space", true), false);
+    assert!(matches!(press, KeyRoute::Consumed(Some(InputEvent::ToggleActive))));
+    // The release belongs to the toggle even though kact is now active
+    assert!(matches!(
+      mapper.route(&keyboard.key("space", false), true),
+      KeyRoute::Consumed(None)
+    ));
+  }
+
+  #[test]
+  fn release_survives_update_bindings() {
+    let (mut mapper, mut keyboard) = (mapper(), Keyboard::default());
+    mapper.route(&keyboard.key("w", true), true);
+
+    let mut config = Config::default();
+    config.keybindings.move_up = KeyCombo::new("i").into();
+    mapper.update_bindings(&config.keybindings, &config.modes);
+
+    assert!(matches!(
+      mapper.route(&keyboard.key("w", false), true),
+      KeyRoute::Consumed(Some(InputEvent::DirectionReleased(Direction::Up)))
+    ));
+    assert!(matches!(
+      mapper.route(&keyboard.key("w", true), true),
+      KeyRoute::PassThrough
+    ));
+    assert!(matches!(
+      mapper.route(&keyboard.key("i", true), true),
+      KeyRoute::Consumed(Some(InputEvent::DirectionPressed(Direction::Up)))
+    ));
+  }
+
+  #[test]
+  fn duplicate_bindings_conflict() {
+    let mut config = Config::default();
+    config.keybindings.move_up = KeyCombo::new("d").into();
+
+    let conflicts = find_conflicts(&config.keybindings, &config.modes);
+    assert_eq!(conflicts.len(), 1);
+    let conflict = &conflicts[0];
+    assert_eq!(conflict.kind, ConflictKind::Duplicate);
+    assert!(conflict.is_error());
+    assert_eq!(conflict.first.0, Action::Move(Direction::Up));
+    assert_eq!(conflict.second.0, Action::Move(Direction::Right));
+    assert_eq!(conflict.to_string(), "'d' is bound to both move_up and move_right");
+  }
+
+  #[test]
+  fn modifier_binding_shadows_chord() {
+    let mut config = Config::default();
+    config.keybindings.cancel_glide = KeyCombo::new("control_l").into();
+    config.keybindings.move_up = "ctrl+w".parse::<KeyCombo>().unwrap().into();
+
+    let conflicts = find_conflicts(&config.keybindings, &config.modes);
+    let shadowed: Vec<_> = conflicts
+      .iter()
+      .filter(|conflict| conflict.second.0 == Action::Move(Direction::Up))
+      .collect();
+    assert_eq!(shadowed.len(), 1);
+    assert_eq!(shadowed[0].kind, ConflictKind::Shadowed);
+    assert!(!shadowed[0].is_error());
+    assert_eq!(shadowed[0].first.0, Action::CancelGlide);
+  }
+
+  #[test]
+  fn every_key_in_a_binding_array_routes() {
+    let source =
+      include_str!("../../kact.toml").replace("move_left = \"a\"", "move_left = [\"a\", \"h\", \"Left\", \"KP_4\"]");
+    let config: Config = toml::from_str(&source).unwrap();
+    let (mut mapper, mut keyboard) = (KeyMapper::new(&config.keybindings, &config.modes), Keyboard::default());
+
+    for key in ["a", "h", "left", "kp_4"] {
+      let press = mapper.route(&keyboard.key(key, true), true);
+      assert!(
+        matches!(
+          press,
+          KeyRoute::Consumed(Some(InputEvent::DirectionPressed(Direction::Left)))
+        ),
+        "{} did not move left",
+        key
+      );
+      mapper.route(&keyboard.key(key, false), true);
+    }
+  }
+}
@@ -0,0 +1,338 @@
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Canonical key names, as reported by the input backends
+#[rustfmt::skip]
+pub const KEY_NAMES: &[&str] = &[
+  "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m",
+  "n", "o", "p", "q", "r", "s", "t", "u", "v", "w", "x", "y", "z",
+  "0", "1", "2", "3", "4", "5", "6", "7", "8", "9",
+  "f1", "f2", "f3", "f4", "f5", "f6", "f7", "f8", "f9", "f10", "f11", "f12",
+  "space", "escape", "return", "tab", "backspace", "delete", "insert",
+  "home", "end", "page_up", "page_down", "up", "down", "left", "right",
+  "minus", "equal", "bracketleft", "bracketright", "semicolon", "apostrophe",
+  "grave", "backslash", "comma", "period", "slash", "caps_lock",
+  "kp_0", "kp_1", "kp_2", "kp_3", "kp_4", "kp_5", "kp_6", "kp_7", "kp_8", "kp_9",
+  "kp_add", "kp_subtract", "kp_multiply", "kp_divide", "kp_decimal", "kp_enter",
+  "shift_l", "shift_r", "control_l", "control_r", "alt_l", "alt_r", "super_l", "super_r",
+];
+
+/// Alternative spellings accepted in bindings, mapped to their canonical name
+const KEY_ALIASES: &[(&str, &str)] = &[
+  ("esc", "escape"),
+  ("enter", "return"),
+  ("del", "delete"),
+  ("ins", "insert"),
+  ("pageup", "page_up"),
+  ("prior", "page_up"),
+  ("pagedown", "page_down"),
+  ("next", "page_down"),
+  ("capslock", "caps_lock"),
+  ("-", "minus"),
+  ("=", "equal"),
+  ("[", "bracketleft"),
+  ("]", "bracketright"),
+  (";", "semicolon"),
+  ("'", "apostrophe"),
+  ("`", "grave"),
+  ("\\", "backslash"),
+  (",", "comma"),
+  (".", "period"),
+  ("/", "slash"),
+];
+
+/// Resolves a key name or alias (case-insensitive) to its canonical name
+pub fn canonical_key_name(name: &str) -> Option<&'static str> {
+  let lower = name.to_ascii_lowercase();
+  if let Some((_, canonical)) = KEY_ALIASES.iter().find(|(alias, _)| *alias == lower) {
+    return Some(canonical);
+  }
+  KEY_NAMES.iter().find(|known| **known == lower).copied()
+}
+
+/// Logical modifier keys, without distinguishing left from right
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Modifiers {
+  pub ctrl: bool,
+  pub alt: bool,
+  pub shift: bool,
+  pub super_key: bool,
+}
+
+impl Modifiers {
+  pub fn is_empty(&self) -> bool {
+    *self == Self::default()
+  }
+
+  /// The modifier a key produces when held, e.g. `shift` for "shift_r"
+  pub fn of_key(key: &str) -> Self {
+    ModifierTracker::modifiers(ModifierTracker::bit(key))
+  }
+
+  /// Whether every modifier in `other` is also held in `self`
+  pub fn contains(&self, other: Modifiers) -> bool {
+    (!other.ctrl || self.ctrl)
+      && (!other.alt || self.alt)
+      && (!other.shift || self.shift)
+      && (!other.super_key || self.super_key)
+  }
+}
+
+/// Tracks held modifier keys from a stream of key events.
+///
+/// Left and right variants are tracked separately so that releasing one side
+/// while the other is still down keeps the modifier active.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ModifierTracker {
+  held: u8,
+}
+
+impl ModifierTracker {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  fn bit(key: &str) -> u8 {
+    match key {
+      "control_l" => 1 << 0,
+      "control_r" => 1 << 1,
+      "alt_l" => 1 << 2,
+      "alt_r" => 1 << 3,
+      "shift_l" => 1 << 4,
+      "shift_r" => 1 << 5,
+      "super_l" => 1 << 6,
+      "super_r" => 1 << 7,
+      _ => 0,
+    }
+  }
+
+  fn modifiers(held: u8) -> Modifiers {
+    Modifiers {
+      ctrl: held & 0b0000_0011 != 0,
+      alt: held & 0b0000_1100 != 0,
+      shift: held & 0b0011_0000 != 0,
+      super_key: held & 0b1100_0000 != 0,
+    }
+  }
+
+  /// Records a key event and returns the modifiers held alongside it.
+  ///
+  /// The key itself is left out, so pressing "shift_l" alone reports no modifiers.
+  pub fn update(&mut self, key: &str, pressed: bool) -> Modifiers {
+    let bit = Self::bit(key);
+    if pressed {
+      self.held |= bit;
+    } else {
+      self.held &= !bit;
+    }
+    Self::modifiers(self.held & !bit)
+  }
+
+  pub fn current(&self) -> Modifiers {
+    Self::modifiers(self.held)
+  }
+}
+
+/// A key with the modifiers that must be held with it, e.g. "ctrl+alt+k"
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+  pub modifiers: Modifiers,
+  /// Canonical key name from `KEY_NAMES`
+  pub key: &'static str,
+}
+
+impl KeyCombo {
+  pub fn new(key: &'static str) -> Self {
+    Self {
+      modifiers: Modifiers::default(),
+      key,
+    }
+  }
+
+  pub fn matches(&self, key: &str, modifiers: Modifiers) -> bool {
+    self.key == key && self.modifiers == modifiers
+  }
+}
+
+impl FromStr for KeyCombo {
+  type Err = String;
+
+  fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+    let parts: Vec<&str> = s.split('+').map(str::trim).collect();
+    let (key, modifier_names) = parts.split_last().ok_or_else(|| "empty key binding".to_string())?;
+
+    let mut modifiers = Modifiers::default();
+    for name in modifier_names {
+      let flag = match name.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" => &mut modifiers.ctrl,
+        "alt" | "meta" | "option" => &mut modifiers.alt,
+        "shift" => &mut modifiers.shift,
+        "super" | "win" | "cmd" | "logo" => &mut modifiers.super_key,
+        _ => {
+          return Err(format!(
+            "unknown modifier '{}' in '{}' (expected ctrl, alt, shift or super)",
+            name, s
+          ));
+        }
+      };
+      if *flag {
+        return Err(format!("modifier '{}' repeated in '{}'", name, s));
+      }
+      *flag = true;
+    }
+
+    let key = canonical_key_name(key).ok_or_else(|| format!("unknown key name '{}' in '{}'", key, s))?;
+    Ok(Self { modifiers, key })
+  }
+}
+
+impl Serialize for KeyCombo {
+  fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+impl<'de> Deserialize<'de> for KeyCombo {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    s.parse().map_err(serde::de::Error::custom)
+  }
+}
+
+impl fmt::Display for KeyCombo {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let m = &self.modifiers;
+    for (held, name) in [
+      (m.ctrl, "ctrl"),
+      (m.alt, "alt"),
+      (m.shift, "shift"),
+      (m.super_key, "super"),
+    ] {
+      if held {
+        write!(f, "{}+", name)?;
+      }
+    }
+    write!(f, "{}", self.key)
+  }
+}
+
+/// All key combinations bound to one action.
+///
+/// Deserializes from a single string or an array of strings, so both
+/// `move_left = "a"` and `move_left = ["a", "h", "Left", "KP_4"]` are accepted.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Binding(pub Vec<KeyCombo>);
+
+impl Binding {
+  pub fn combos(&self) -> &[KeyCombo] {
+    &self.0
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+}
+
+impl From<KeyCombo> for Binding {
+  fn from(combo: KeyCombo) -> Self {
+    Self(vec![combo])
+  }
+}
+
+impl Serialize for Binding {
+  fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    if let [combo] = self.0.as_slice() {
+      return combo.serialize(serializer);
+    }
+    let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+    for combo in &self.0 {
+      seq.serialize_element(combo)?;
+    }
+    seq.end()
+  }
+}
+
+impl<'de> Deserialize<'de> for Binding {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+    struct BindingVisitor;
+
+    impl<'de> Visitor<'de> for BindingVisitor {
+      type Value = Binding;
+
+      fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a key binding string or an array of them")
+      }
+
+      fn visit_str<E: de::Error>(self, s: &str) -> std::result::Result<Binding, E> {
+        s.parse::<KeyCombo>().map(Binding::from).map_err(E::custom)
+      }
+
+      fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Binding, A::Error> {
+        let mut combos = Vec::new();
+        while let Some(combo) = seq.next_element()? {
+          combos.push(combo);
+        }
+        Ok(Binding(combos))
+      }
+    }
+
+    deserializer.deserialize_any(BindingVisitor)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn combo(s: &str) -> KeyCombo {
+    s.parse().unwrap()
+  }
+
+  #[test]
+  fn combos_parse_modifiers_and_key() {
+    let c = combo("ctrl+alt+k");
+    assert_eq!(c.key, "k");
+    assert!(c.modifiers.ctrl && c.modifiers.alt);
+    assert!(!c.modifiers.shift && !c.modifiers.super_key);
+
+    let c = combo("super+space");
+    assert_eq!(c.key, "space");
+    assert!(c.modifiers.super_key && !c.modifiers.ctrl);
+
+    let c = combo("shift+Left");
+    assert_eq!(c.key, "left");
+    assert!(c.modifiers.shift);
+  }
+
+  #[test]
+  fn aliases_resolve_to_canonical_names() {
+    assert_eq!(combo("Control+Esc"), combo("ctrl+escape"));
+    assert_eq!(combo("cmd+Enter"), combo("super+return"));
+    assert_eq!(combo("option+PageUp"), combo("alt+page_up"));
+    assert_eq!(combo("KP_4").key, "kp_4");
+  }
+
+  #[test]
+  fn malformed_combos_are_rejected() {
+    for bad in ["ctrl+nosuchkey", "hyper+k", "ctrl+control+k", "ctrl+", ""] {
+      assert!(bad.parse::<KeyCombo>().is_err(), "'{}' should not parse", bad);
+    }
+  }
+
+  #[test]
+  fn display_round_trips_through_parse() {
+    for s in [
+      "k",
+      "ctrl+alt+k",
+      "super+space",
+      "shift+Left",
+      "ctrl+alt+shift+super+kp_9",
+    ] {
+      let c = combo(s);
+      assert_eq!(combo(&c.to_string()), c);
+    }
+  }
+}
@@ -1,91 +1,549 @@
-use super::state::{AppState, Mode};
+use super::accel::AccelProfile;
+use super::curve::{Curve, Linear};
+use super::state::{AppState, Glide, InputState, Mode, MotionState};
 use super::types::Vector2D;
-use crate::config::MotionConfig;
+use crate::config::{ModeConfig, MotionConfig, Reversal};
+use std::collections::HashMap;
+use std::time::Instant;
 
-pub struct MotionEngine {
+/// Motion settings for one mode, with its overrides applied and its curve built
+struct Profile {
   config: MotionConfig,
+  multiplier: f64,
+  curve: Box<dyn Curve>,
+  accel: Option<Box<dyn AccelProfile>>,
+}
+
+impl Profile {
+  fn new(config: MotionConfig, multiplier: f64) -> Self {
+    // Curves are checked when the config is loaded, so this only guards hand-built configs
+    let curve = config.curve_type.build(config.acceleration).unwrap_or_else(|e| {
+      tracing::error!("Invalid curve, using linear: {}", e);
+      Box::new(Linear)
+    });
+    let accel = config.curve_type.accel_profile(config.libinput_speed);
+    Self {
+      config,
+      multiplier,
+      curve,
+      accel,
+    }
+  }
+}
+
+/// Longest step simulated at once; longer gaps (suspend, stalls) are cut short
+/// instead of flinging the cursor across the screen
+pub const MAX_DELTA_TIME: f64 = 0.1;
+
+/// `acceleration` is the fraction of the gap to the target speed closed per 1/60 s
+const ACCELERATION_REFERENCE_HZ: f64 = 60.0;
+
+pub struct MotionEngine {
+  base: Profile,
+  profiles: HashMap<Mode, Profile>,
+  /// Mode that takes over after a reversal under `Reversal::Precise`
+  reversal_mode: Mode,
 }
 
 impl MotionEngine {
-  pub fn new(config: MotionConfig) -> Self {
-    Self { config }
+  pub fn new(config: MotionConfig, modes: &ModeConfig) -> Self {
+    Self {
+      profiles: Self::build_profiles(&config, modes),
+      reversal_mode: Mode::new(config.reversal_mode.as_str()),
+      base: Profile::new(config, 1.0),
+    }
+  }
+
+  pub fn update_config(&mut self, config: MotionConfig, modes: &ModeConfig) {
+    *self = Self::new(config, modes);
+  }
+
+  fn build_profiles(config: &MotionConfig, modes: &ModeConfig) -> HashMap<Mode, Profile> {
+    modes
+      .iter()
+      .map(|(name, mode)| {
+        (
+          Mode::new(name.as_str()),
+          Profile::new(mode.apply(config), mode.multiplier),
+        )
+      })
+      .collect()
   }
 
-  pub fn update_config(&mut self, config: MotionConfig) {
-    self.config = config;
+  /// Profile for a mode; unknown modes run on the base settings
+  fn profile(&self, mode: &Mode) -> &Profile {
+    self.profiles.get(mode).unwrap_or(&self.base)
   }
 
-  /// Pure function: (State, DeltaTime) -> (NewVelocity, DeltaPosition)
-  pub fn tick(&self, state: &AppState, delta_time: f64) -> (Vector2D, Vector2D) {
+  /// Pure function: (State, DeltaTime, Now) -> (NewMotionState, DeltaPosition)
+  pub fn tick(&self, state: &AppState, delta_time: f64, now: Instant) -> (MotionState, Vector2D) {
     if !state.active || state.emergency_stop {
-      return (Vector2D::zero(), Vector2D::zero());
+      return (MotionState::default(), Vector2D::zero());
     }
 
-    let input_vector = state.input.get_input_vector();
-    let mode_multiplier = self.get_mode_multiplier(state.input.mode);
+    // In scroll mode the pointer coasts to a stop while the keys drive the wheel
+    let input = if state.scroll_mode {
+      &InputState::new()
+    } else {
+      &state.input
+    };
+    self.step(input, &state.motion, delta_time, now)
+  }
+
+  /// Like `tick`, but for the scroll motion; deltas are in wheel notches
+  pub fn tick_scroll(&self, state: &AppState, delta_time: f64, now: Instant) -> (MotionState, Vector2D) {
+    if !state.active || state.emergency_stop {
+      return (MotionState::default(), Vector2D::zero());
+    }
 
-    // Calculate target velocity based on input
-    let target_velocity = if input_vector.magnitude() > 0.0 {
-      let curve_factor = self.apply_curve(input_vector.magnitude());
-      input_vector.scale(self.config.max_speed * curve_factor * mode_multiplier)
+    let input = if state.scroll_mode {
+      &state.input
     } else {
-      Vector2D::zero()
+      &InputState::new()
+    };
+    self.step(input, &state.scroll_motion, delta_time, now)
+  }
+
+  /// Advances `motion` by `delta_time` seconds under `input`: (NewMotionState, DeltaPosition).
+  ///
+  /// Velocity approaches its target exponentially and is integrated exactly over
+  /// the step, so trajectories do not depend on the frame rate. Releasing the keys
+  /// at `fling_speed` or faster starts a glide instead of the `decay`, and
+  /// pressing against the motion turns around according to the reversal policy.
+  pub fn step(
+    &self,
+    input: &InputState,
+    motion: &MotionState,
+    delta_time: f64,
+    now: Instant,
+  ) -> (MotionState, Vector2D) {
+    let delta_time = delta_time.clamp(0.0, MAX_DELTA_TIME);
+    let input_vector = input.get_input_vector();
+
+    // Pressing against the current motion, typically to correct an overshoot
+    let against = motion.velocity.dot(&input_vector);
+    let reversing = against < 0.0;
+    let policy = self.base.config.reversal;
+    let reversal = if policy != Reversal::Precise || input_vector.magnitude() == 0.0 {
+      None
+    } else if reversing {
+      Some(0.0)
+    } else {
+      let reversal_time = self.base.config.reversal_time;
+      motion
+        .reversal
+        .map(|elapsed| elapsed + delta_time)
+        .filter(|elapsed| *elapsed < reversal_time)
+    };
+    let mode = if reversal.is_some() {
+      &self.reversal_mode
+    } else {
+      input.current_mode()
+    };
+
+    let profile = self.profile(mode);
+    let config = &profile.config;
+    let mut motion = *motion;
+    // The jerk-limited profile never jumps; it turns as fast as its limits allow
+    if reversing && config.max_acceleration.is_none() && matches!(policy, Reversal::Instant | Reversal::Precise) {
+      // Keep only the motion that does not oppose the pressed direction
+      motion.velocity = motion.velocity.sub(&input_vector.scale(against));
+    }
+    let velocity = &motion.velocity;
+
+    if input_vector.magnitude() == 0.0 {
+      let glide = motion.glide.or_else(|| {
+        (config.coast_time > 0.0 && velocity.magnitude() >= config.fling_speed).then_some(Glide {
+          velocity: *velocity,
+          elapsed: 0.0,
+        })
+      });
+      if let Some(glide) = glide {
+        return match config.max_acceleration {
+          Some(max_acceleration) => Self::jerk_limited_glide(&motion, &glide, config, max_acceleration, delta_time),
+          None => Self::coast(&glide, config.coast_time, delta_time),
+        };
+      }
+    }
+
+    // Calculate target velocity based on how long the keys have been held, at
+    // both ends of the step so the ramp can be followed within it
+    let target_at = |held: f64| {
+      let speed = Self::hold_speed(profile, held) * profile.multiplier;
+      // libinput works in units per millisecond
+      let factor = profile.accel.as_ref().map_or(1.0, |accel| accel.factor(speed / 1000.0));
+      Self::scale_axes(config, &input_vector.scale(speed * factor))
+    };
+    let (start_target, target_velocity) = match input.hold_duration(now) {
+      Some(held) if input_vector.magnitude() > 0.0 => {
+        let held = held.as_secs_f64();
+        (target_at((held - delta_time).max(0.0)), target_at(held))
+      }
+      _ => (Vector2D::zero(), Vector2D::zero()),
+    };
+
+    // Approach the target at the acceleration rate; decay adds to it once keys
+    // are released, and braking while turning around under `Reversal::Brake`
+    let rate = if input_vector.magnitude() == 0.0 {
+      Self::acceleration_rate(config) + config.decay.max(0.0)
+    } else if reversing && policy == Reversal::Brake {
+      Self::acceleration_rate(config) + config.reversal_brake.max(0.0)
+    } else {
+      Self::acceleration_rate(config)
+    };
+
+    let (mut state, delta_position) = match config.max_acceleration {
+      Some(max_acceleration) => {
+        Self::jerk_limited(&motion, &target_velocity, max_acceleration, config.max_jerk, delta_time)
+      }
+      None => {
+        let (velocity, delta_position) = Self::approach(velocity, &start_target, &target_velocity, rate, delta_time);
+        let state = MotionState {
+          velocity,
+          ..MotionState::default()
+        };
+        (state, delta_position)
+      }
+    };
+    state.reversal = reversal;
+    (state, delta_position)
+  }
+
+  /// Steers velocity towards `target` with acceleration capped at `max_acceleration`
+  /// and its rate of change capped at `max_jerk`, giving S-shaped starts and stops.
+  ///
+  /// The wanted acceleration shrinks as sqrt(2·jerk·error) near the target, so it
+  /// can be wound down to zero within the jerk limit just as the velocity arrives.
+  fn jerk_limited(
+    motion: &MotionState,
+    target: &Vector2D,
+    max_acceleration: f64,
+    max_jerk: Option<f64>,
+    delta_time: f64,
+  ) -> (MotionState, Vector2D) {
+    let error = target.sub(&motion.velocity);
+    let wanted_magnitude = match max_jerk {
+      Some(jerk) => max_acceleration.min((2.0 * jerk * error.magnitude()).sqrt()),
+      None => max_acceleration,
+    };
+    // Never ask for more than closes the gap within this step
+    let wanted_magnitude = if delta_time > 0.0 {
+      wanted_magnitude.min(error.magnitude() / delta_time)
+    } else {
+      wanted_magnitude
+    };
+    let wanted = error.normalize().scale(wanted_magnitude);
+
+    let acceleration = match max_jerk {
+      Some(jerk) => {
+        let change = wanted.sub(&motion.acceleration).clamp_magnitude(jerk * delta_time);
+        motion.acceleration.add(&change)
+      }
+      None => wanted,
+    }
+    .clamp_magnitude(max_acceleration);
+
+    // Trapezoidal integration of the linearly changing acceleration
+    let velocity = motion
+      .velocity
+      .add(&motion.acceleration.add(&acceleration).scale(delta_time / 2.0));
+    let delta_position = motion.velocity.add(&velocity).scale(delta_time / 2.0);
+
+    let state = MotionState {
+      velocity,
+      acceleration,
+      ..MotionState::default()
     };
+    (state, delta_position)
+  }
+
+  /// Follows a glide's linear slowdown as closely as the jerk-limited profile
+  /// allows, so flings keep within `max_acceleration` and `max_jerk` too.
+  ///
+  /// The glide lasts until its time is up and the cursor is below `fling_speed`;
+  /// from there the profile brings it to rest without starting a new glide.
+  fn jerk_limited_glide(
+    motion: &MotionState,
+    glide: &Glide,
+    config: &MotionConfig,
+    max_acceleration: f64,
+    delta_time: f64,
+  ) -> (MotionState, Vector2D) {
+    let elapsed = (glide.elapsed + delta_time).min(config.coast_time);
+    let target = glide.velocity.scale(1.0 - elapsed / config.coast_time);
+    let (mut state, delta_position) =
+      Self::jerk_limited(motion, &target, max_acceleration, config.max_jerk, delta_time);
+
+    if elapsed < config.coast_time || state.velocity.magnitude() >= config.fling_speed {
+      state.glide = Some(Glide {
+        velocity: glide.velocity,
+        elapsed,
+      });
+    }
+    (state, delta_position)
+  }
+
+  /// Advances a glide whose speed falls linearly to zero over `coast_time`,
+  /// covering `velocity * coast_time / 2` in total
+  fn coast(glide: &Glide, coast_time: f64, delta_time: f64) -> (MotionState, Vector2D) {
+    let start = glide.elapsed.min(coast_time);
+    let end = (glide.elapsed + delta_time).min(coast_time);
+    // Integral of (1 - t / T) dt
+    let covered = |t: f64| t - t * t / (2.0 * coast_time);
+    let delta_position = glide.velocity.scale(covered(end) - covered(start));
 
-    // Interpolate towards target velocity (acceleration)
-    let new_velocity = self.lerp_velocity(&state.velocity, &target_velocity, delta_time);
+    if end >= coast_time {
+      return (MotionState::default(), delta_position);
+    }
+    let state = MotionState {
+      velocity: glide.velocity.scale(1.0 - end / coast_time),
+      acceleration: glide.velocity.scale(-1.0 / coast_time),
+      glide: Some(Glide {
+        velocity: glide.velocity,
+        elapsed: end,
+      }),
+      reversal: None,
+    };
+    (state, delta_position)
+  }
 
-    // Apply friction when no input
-    let new_velocity = if input_vector.magnitude() == 0.0 {
-      new_velocity.scale(self.config.friction)
+  /// Speed after holding a direction for `held` seconds, ramping from
+  /// `initial_speed` to `max_speed` over `ramp_time` along the profile's curve
+  fn hold_speed(profile: &Profile, held: f64) -> f64 {
+    let config = &profile.config;
+    let progress = if config.ramp_time > 0.0 {
+      (held / config.ramp_time).clamp(0.0, 1.0)
     } else {
-      new_velocity
+      1.0
     };
+    let initial = config.initial_speed.min(config.max_speed);
+    initial + (config.max_speed - initial) * profile.curve.apply(progress)
+  }
 
-    // Calculate position delta
-    let delta_position = new_velocity.scale(delta_time);
+  /// Applies the per-axis speed multipliers, then slows the whole velocity down
+  /// until neither axis exceeds its cap, so diagonals keep their direction
+  fn scale_axes(config: &MotionConfig, velocity: &Vector2D) -> Vector2D {
+    let scaled = Vector2D::new(velocity.x * config.speed_scale_x, velocity.y * config.speed_scale_y);
+    let fit = |speed: f64, max: Option<f64>| match max {
+      Some(max) if speed.abs() > max => max / speed.abs(),
+      _ => 1.0,
+    };
+    let factor = fit(scaled.x, config.max_speed_x).min(fit(scaled.y, config.max_speed_y));
+    scaled.scale(factor)
+  }
 
-    (new_velocity, delta_position)
+  /// Exponential approach rate per second equivalent to `acceleration`
+  fn acceleration_rate(config: &MotionConfig) -> f64 {
+    let acceleration = config.acceleration.clamp(0.0, 1.0);
+    -ACCELERATION_REFERENCE_HZ * (1.0 - acceleration).ln()
   }
 
-  fn get_mode_multiplier(&self, mode: Mode) -> f64 {
-    // Note: These should come from config.modes in a real implementation
-    // For now, using hardcoded values
-    match mode {
-      Mode::Normal => 1.0,
-      Mode::Precise => 0.3,
-      Mode::Fast => 2.5,
+  /// Moves `velocity` towards a target that changes linearly from `start` to `end`
+  /// over the step, solving v' = rate·(target(t) - v) exactly, and returns the
+  /// velocity and the distance covered after `delta_time`
+  fn approach(
+    velocity: &Vector2D,
+    start: &Vector2D,
+    end: &Vector2D,
+    rate: f64,
+    delta_time: f64,
+  ) -> (Vector2D, Vector2D) {
+    if rate.is_infinite() {
+      return (*end, start.add(end).scale(delta_time / 2.0));
     }
+    if rate <= 0.0 || delta_time <= 0.0 {
+      return (*velocity, velocity.scale(delta_time));
+    }
+
+    // With target(t) = start + slope·t, the gap v - target settles at -slope/rate
+    let slope = end.sub(start).scale(1.0 / delta_time);
+    let lag = slope.scale(-1.0 / rate);
+    let decay = (-rate * delta_time).exp();
+    let transient = velocity.sub(start).sub(&lag);
+
+    let new_velocity = end.add(&lag).add(&transient.scale(decay));
+    let distance = start
+      .add(end)
+      .scale(delta_time / 2.0)
+      .add(&lag.scale(delta_time))
+      .add(&transient.scale((1.0 - decay) / rate));
+    (new_velocity, distance)
   }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::Config;
+  use crate::core::types::Direction;
+  use std::time::Duration;
+
+  fn engine(config: &Config) -> MotionEngine {
+    MotionEngine::new(config.motion.clone(), &config.modes)
+  }
+
+  /// Holds `right` for `hold` seconds, then lets go, stepping at `fps` for `total`
+  /// seconds; calls `inspect` with each new state and returns the distance covered
+  fn simulate(
+    engine: &MotionEngine,
+    fps: f64,
+    hold: f64,
+    total: f64,
+    mut inspect: impl FnMut(&MotionState, &MotionState, f64),
+  ) -> Vector2D {
+    let mut state = AppState::new();
+    state.active = true;
+    state.input.press_direction(Direction::Right);
+    let start = Instant::now();
+    let delta_time = 1.0 / fps;
+    let mut position = Vector2D::zero();
 
-  fn apply_curve(&self, input_magnitude: f64) -> f64 {
-    match self.config.curve_type.as_str() {
-      "sigmoid" => self.sigmoid_curve(input_magnitude),
-      "exponential" => self.exponential_curve(input_magnitude),
-      "linear" => input_magnitude,
-      _ => input_magnitude,
+    for frame in 1..=(total * fps).round() as u32 {
+      if frame as f64 * delta_time > hold + 1e-9 {
+        state.input.release_direction(Direction::Right);
+      }
+      let now = start + Duration::from_secs_f64(frame as f64 * delta_time);
+      let (motion, delta) = engine.tick(&state, delta_time, now);
+      inspect(&state.motion, &motion, delta_time);
+      state.motion = motion;
+      position = position.add(&delta);
     }
+    position
   }
 
-  fn sigmoid_curve(&self, x: f64) -> f64 {
-    // Sigmoid: 1 / (1 + e^(-k(x - 0.5)))
-    // Maps [0, 1] to smooth S-curve
-    let k = 10.0 * self.config.acceleration;
-    1.0 / (1.0 + (-k * (x - 0.5)).exp())
+  #[test]
+  fn trajectory_does_not_depend_on_frame_rate() {
+    let config = Config::default();
+    let engine = engine(&config);
+
+    // Long enough to reach a fling, and a short tap that only decays;
+    // both are whole frames at every rate
+    for hold in [1.0, 1.0 / 6.0] {
+      let reference = simulate(&engine, 144.0, hold, 2.0, |_, _, _| {});
+      for fps in [30.0, 60.0] {
+        let distance = simulate(&engine, fps, hold, 2.0, |_, _, _| {});
+        let error = distance.sub(&reference).magnitude() / reference.magnitude();
+        assert!(
+          error < 0.01,
+          "{} fps covered {:?}, 144 fps {:?}",
+          fps,
+          distance,
+          reference
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn long_steps_are_clamped() {
+    let engine = engine(&Config::default());
+    let motion = MotionState {
+      velocity: Vector2D::new(1000.0, 0.0),
+      ..MotionState::default()
+    };
+    let input = InputState::new();
+    let now = Instant::now();
+
+    let clamped = engine.step(&input, &motion, 5.0, now);
+    let longest = engine.step(&input, &motion, MAX_DELTA_TIME, now);
+    assert_eq!(clamped, longest);
+  }
+
+  /// Steps a press, hold and release under the jerk-limited profile and checks the
+  /// limits on every tick; returns whether a glide happened and the final state
+  fn check_jerk_limits(hold: f64, fps: f64) -> (bool, MotionState) {
+    let (max_acceleration, max_jerk) = (3000.0, 20000.0);
+    let mut config = Config::default();
+    config.motion.max_acceleration = Some(max_acceleration);
+    config.motion.max_jerk = Some(max_jerk);
+    let engine = engine(&config);
+
+    let mut glided = false;
+    let mut last = MotionState::default();
+    simulate(&engine, fps, hold, hold + 3.0, |old, new, delta_time| {
+      let acceleration = new.acceleration.magnitude();
+      let jerk = new.acceleration.sub(&old.acceleration).magnitude() / delta_time;
+      assert!(
+        acceleration <= max_acceleration * (1.0 + 1e-9),
+        "|a| = {}",
+        acceleration
+      );
+      assert!(jerk <= max_jerk * (1.0 + 1e-9), "|j| = {}", jerk);
+      glided |= new.glide.is_some();
+      last = *new;
+    });
+    (glided, last)
+  }
+
+  #[test]
+  fn jerk_limits_hold_through_a_fling() {
+    for fps in [60.0, 144.0] {
+      let (glided, last) = check_jerk_limits(1.0, fps);
+      assert!(glided, "no fling at {} fps", fps);
+      assert!(last.velocity.magnitude() < 1.0, "still moving at {:?}", last.velocity);
+    }
   }
 
-  fn exponential_curve(&self, x: f64) -> f64 {
-    // Exponential: x^p where p controls steepness
-    let power = 1.0 + (1.0 - self.config.acceleration) * 2.0;
-    x.powf(power)
+  #[test]
+  fn jerk_limits_hold_on_a_plain_release() {
+    for fps in [60.0, 144.0] {
+      let (glided, last) = check_jerk_limits(0.1, fps);
+      assert!(!glided, "short tap flung at {} fps", fps);
+      assert!(last.velocity.magnitude() < 1.0, "still moving at {:?}", last.velocity);
+    }
   }
 
-  fn lerp_velocity(&self, current: &Vector2D, target: &Vector2D, delta_time: f64) -> Vector2D {
-    // Smooth interpolation with acceleration factor
-    let t = 1.0 - (1.0 - self.config.acceleration).powf(delta_time * 60.0);
-    Vector2D::new(
-      current.x + (target.x - current.x) * t,
-      current.y + (target.y - current.y) * t,
-    )
+  /// One step with `left` held while moving right at 1000 px/s
+  fn reverse(config: &Config) -> MotionState {
+    let engine = engine(config);
+    let mut input = InputState::new();
+    input.press_direction(Direction::Left);
+    let motion = MotionState {
+      velocity: Vector2D::new(1000.0, 0.0),
+      ..MotionState::default()
+    };
+    engine.step(&input, &motion, 1.0 / 144.0, Instant::now()).0
+  }
+
+  #[test]
+  fn instant_reversal_drops_opposing_motion() {
+    let mut config = Config::default();
+    config.motion.reversal = Reversal::Instant;
+    assert!(reverse(&config).velocity.x <= 0.0);
+  }
+
+  #[test]
+  fn reversal_keeps_jerk_limits() {
+    let (max_acceleration, max_jerk) = (3000.0, 20000.0);
+    for policy in [Reversal::Instant, Reversal::Precise] {
+      let mut config = Config::default();
+      config.motion.reversal = policy;
+      config.motion.max_acceleration = Some(max_acceleration);
+      config.motion.max_jerk = Some(max_jerk);
+
+      let state = reverse(&config);
+      // Starting from zero acceleration, one step can add at most max_jerk * dt
+      assert!(state.acceleration.magnitude() <= max_jerk / 144.0 * (1.0 + 1e-9));
+      assert!(state.velocity.x > 0.0);
+      assert_eq!(state.reversal.is_some(), policy == Reversal::Precise);
+    }
+  }
+
+  #[test]
+  fn capped_diagonal_keeps_its_direction() {
+    let mut config = Config::default().motion;
+    config.speed_scale_x = 1.5;
+    config.speed_scale_y = 0.5;
+    config.max_speed_x = Some(600.0);
+    config.max_speed_y = Some(100.0);
+
+    for velocity in [Vector2D::new(1000.0, -1000.0), Vector2D::new(-300.0, 900.0)] {
+      let scaled = MotionEngine::scale_axes(&config, &velocity);
+      assert!(scaled.x.abs() <= 600.0 + 1e-9 && scaled.y.abs() <= 100.0 + 1e-9);
+      // Same heading as the scaled but uncapped velocity: the cap only shortens it
+      let uncapped = Vector2D::new(velocity.x * 1.5, velocity.y * 0.5);
+      assert!((scaled.x * uncapped.y - scaled.y * uncapped.x).abs() < 1e-6);
+      assert!(scaled.dot(&uncapped) > 0.0);
+    }
   }
 }
@@ -1,23 +1,43 @@
-use super::types::{Direction, Vector2D};
-use std::collections::HashSet;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Mode {
-  Normal,
-  Precise,
-  Fast,
+use super::types::{Direction, MouseButton, Vector2D};
+use crate::config::DEFAULT_MODE;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Name of a mode defined in the `[modes]` table
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Mode(pub String);
+
+impl Mode {
+  pub fn new(name: impl Into<String>) -> Self {
+    Self(name.into())
+  }
+
+  pub fn name(&self) -> &str {
+    &self.0
+  }
 }
 
 impl Default for Mode {
   fn default() -> Self {
-    Mode::Normal
+    Self::new(DEFAULT_MODE)
+  }
+}
+
+impl fmt::Display for Mode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.0)
   }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct InputState {
-  pub active_directions: HashSet<Direction>,
+  /// Held directions with the time each was pressed
+  pub active_directions: HashMap<Direction, Instant>,
+  /// Latched mode, in effect while no hold mode is down
   pub mode: Mode,
+  /// Hold modes whose keys are down, most recent last
+  pub mode_stack: Vec<Mode>,
 }
 
 impl InputState {
@@ -26,7 +46,7 @@ impl InputState {
   }
 
   pub fn press_direction(&mut self, dir: Direction) {
-    self.active_directions.insert(dir);
+    self.active_directions.entry(dir).or_insert_with(Instant::now);
   }
 
   pub fn release_direction(&mut self, dir: Direction) {
@@ -37,14 +57,70 @@ impl InputState {
     self.mode = mode;
   }
 
+  pub fn hold_mode(&mut self, mode: Mode) {
+    self.mode_stack.push(mode);
+  }
+
+  /// Drops a hold mode, wherever it is in the stack, reverting to the one below
+  pub fn release_mode(&mut self, mode: &Mode) {
+    if let Some(index) = self.mode_stack.iter().rposition(|held| held == mode) {
+      self.mode_stack.remove(index);
+    }
+  }
+
+  /// The mode in effect: the most recent hold mode, else the latched one
+  pub fn current_mode(&self) -> &Mode {
+    self.mode_stack.last().unwrap_or(&self.mode)
+  }
+
   pub fn get_input_vector(&self) -> Vector2D {
     let mut result = Vector2D::zero();
-    for dir in &self.active_directions {
+    for dir in self.active_directions.keys() {
       let v = dir.to_vector();
       result = result.add(&v);
     }
+    // Opposite directions cancel; angled ones leave rounding noise that must not pick a direction
+    if result.magnitude() < 1e-9 {
+      return Vector2D::zero();
+    }
     result.normalize()
   }
+
+  /// How long the longest-held direction has been down, if any is
+  pub fn hold_duration(&self, now: Instant) -> Option<Duration> {
+    self
+      .active_directions
+      .values()
+      .min()
+      .map(|pressed| now.saturating_duration_since(*pressed))
+  }
+}
+
+/// Coasting after a fling: speed falls linearly from the release velocity to zero
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glide {
+  /// Velocity when the keys were released
+  pub velocity: Vector2D,
+  /// Seconds since the glide started
+  pub elapsed: f64,
+}
+
+/// Physics state one `MotionEngine` carries from frame to frame
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MotionState {
+  pub velocity: Vector2D,
+  /// Current acceleration, tracked by the jerk-limited profile
+  pub acceleration: Vector2D,
+  pub glide: Option<Glide>,
+  /// Seconds since the last reversal, while the reversal mode is in effect
+  pub reversal: Option<f64>,
+}
+
+impl MotionState {
+  /// Comes to a standstill immediately, cancelling any glide
+  pub fn stop(&mut self) {
+    *self = Self::default();
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -52,8 +128,16 @@ pub struct AppState {
   pub active: bool,
   pub emergency_stop: bool,
   pub input: InputState,
-  pub velocity: Vector2D,
+  pub motion: MotionState,
   pub position: Vector2D,
+  /// Buttons kact is currently holding down
+  pub held_buttons: HashSet<MouseButton>,
+  /// The left button is latched down by drag lock
+  pub drag_locked: bool,
+  /// Direction keys scroll instead of moving the pointer
+  pub scroll_mode: bool,
+  /// Scroll physics, with velocity in wheel notches per second
+  pub scroll_motion: MotionState,
 }
 
 impl Default for AppState {
@@ -62,8 +146,12 @@ impl Default for AppState {
       active: false,
       emergency_stop: false,
       input: InputState::new(),
-      velocity: Vector2D::zero(),
+      motion: MotionState::default(),
       position: Vector2D::zero(),
+      held_buttons: HashSet::new(),
+      drag_locked: false,
+      scroll_mode: false,
+      scroll_motion: MotionState::default(),
     }
   }
 }
@@ -80,5 +168,94 @@ impl AppState {
   pub fn trigger_emergency_stop(&mut self) {
     self.emergency_stop = true;
     self.active = false;
+    self.drag_locked = false;
+  }
+
+  /// Whether a button release should be held back because drag lock owns the button
+  pub fn is_latched(&self, button: MouseButton) -> bool {
+    self.drag_locked && button == MouseButton::Left
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn releasing_a_hold_returns_to_the_mode_latched_meanwhile() {
+    let mut input = InputState::new();
+    input.hold_mode(Mode::new("fast"));
+    input.set_mode(Mode::new("precise"));
+    // The held mode stays in effect until its key comes up
+    assert_eq!(input.current_mode().name(), "fast");
+
+    input.release_mode(&Mode::new("fast"));
+    assert_eq!(input.current_mode().name(), "precise");
+  }
+
+  #[test]
+  fn nested_holds_can_be_released_out_of_order() {
+    let mut input = InputState::new();
+    input.hold_mode(Mode::new("fast"));
+    input.hold_mode(Mode::new("precise"));
+    assert_eq!(input.current_mode().name(), "precise");
+
+    // Letting go of the outer hold first keeps the inner one
+    input.release_mode(&Mode::new("fast"));
+    assert_eq!(input.current_mode().name(), "precise");
+    input.release_mode(&Mode::new("precise"));
+    assert_eq!(input.current_mode(), &Mode::default());
+    assert!(input.mode_stack.is_empty());
+  }
+
+  fn input_vector(directions: &[Direction]) -> Vector2D {
+    let mut input = InputState::new();
+    for dir in directions {
+      input.press_direction(*dir);
+    }
+    input.get_input_vector()
+  }
+
+  fn assert_close(actual: Vector2D, expected: Vector2D) {
+    assert!(
+      (actual.x - expected.x).abs() < 1e-9 && (actual.y - expected.y).abs() < 1e-9,
+      "{:?} != {:?}",
+      actual,
+      expected
+    );
+  }
+
+  #[test]
+  fn diagonal_and_cardinal_keys_combine() {
+    // KP_9 (move_ne) with Right leans the diagonal towards the right
+    let v = input_vector(&[Direction::UpRight, Direction::Right]);
+    let angle = v.x.atan2(-v.y).to_degrees();
+    assert!((angle - 67.5).abs() < 1e-9, "bearing {}", angle);
+    assert!((v.magnitude() - 1.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn opposite_diagonals_cancel() {
+    assert_eq!(
+      input_vector(&[Direction::UpRight, Direction::DownLeft]),
+      Vector2D::zero()
+    );
+    assert_eq!(
+      input_vector(&[Direction::Custom(30), Direction::Custom(210)]),
+      Vector2D::zero()
+    );
+  }
+
+  #[test]
+  fn custom_angles_match_named_directions() {
+    assert_close(
+      input_vector(&[Direction::Custom(90)]),
+      input_vector(&[Direction::Right]),
+    );
+    assert_close(input_vector(&[Direction::Custom(0)]), input_vector(&[Direction::Up]));
+    assert_close(
+      input_vector(&[Direction::Custom(225)]),
+      input_vector(&[Direction::DownLeft]),
+    );
   }
 }
@@ -44,6 +44,23 @@ impl Vector2D {
       y: self.y + other.y,
     }
   }
+
+  pub fn sub(&self, other: &Vector2D) -> Self {
+    Self {
+      x: self.x - other.x,
+      y: self.y - other.y,
+    }
+  }
+
+  pub fn dot(&self, other: &Vector2D) -> f64 {
+    self.x * other.x + self.y * other.y
+  }
+
+  /// Shortens the vector to at most `max` long, keeping its direction
+  pub fn clamp_magnitude(&self, max: f64) -> Self {
+    let mag = self.magnitude();
+    if mag > max { self.scale(max / mag) } else { *self }
+  }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -52,15 +69,52 @@ pub enum Direction {
   Down,
   Left,
   Right,
+  UpLeft,
+  UpRight,
+  DownLeft,
+  DownRight,
+  /// Compass bearing in whole degrees: 0 is up, 90 is right
+  Custom(u16),
 }
 
 impl Direction {
+  /// Unit vector pointing this way, with +y down as on screen
   pub fn to_vector(&self) -> Vector2D {
     match self {
       Direction::Up => Vector2D::new(0.0, -1.0),
       Direction::Down => Vector2D::new(0.0, 1.0),
       Direction::Left => Vector2D::new(-1.0, 0.0),
       Direction::Right => Vector2D::new(1.0, 0.0),
+      Direction::UpLeft => Vector2D::new(-1.0, -1.0).normalize(),
+      Direction::UpRight => Vector2D::new(1.0, -1.0).normalize(),
+      Direction::DownLeft => Vector2D::new(-1.0, 1.0).normalize(),
+      Direction::DownRight => Vector2D::new(1.0, 1.0).normalize(),
+      Direction::Custom(degrees) => {
+        let radians = f64::from(degrees % 360).to_radians();
+        Vector2D::new(radians.sin(), -radians.cos())
+      }
     }
   }
 }
+
+/// Pointer buttons kact can press
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+  Left,
+  Middle,
+  Right,
+  /// Side button, "back" in browsers
+  Back,
+  /// Extra button, "forward" in browsers
+  Forward,
+}
+
+impl MouseButton {
+  pub const ALL: [MouseButton; 5] = [
+    MouseButton::Left,
+    MouseButton::Middle,
+    MouseButton::Right,
+    MouseButton::Back,
+    MouseButton::Forward,
+  ];
+}
@@ -8,8 +8,14 @@ pub enum Error {
   #[error("TOML parse error: {0}")]
   TomlParse(#[from] toml::de::Error),
 
+  #[error("Invalid configuration: {0}")]
+  Config(String),
+
   #[error("Platform error: {0}")]
   Platform(String),
+
+  #[error("Channel send error")]
+  ChannelSend,
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
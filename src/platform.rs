@@ -1,6 +1,11 @@
 use crate::Result;
-use crate::core::state::Mode;
-use crate::core::types::{Direction, Vector2D};
+use crate::config::Backend;
+use crate::core::types::{MouseButton, Vector2D};
+
+pub use crate::core::keymap::{InputEvent, KeyEvent};
+pub use crate::core::keys::Modifiers;
+
+pub mod subpixel;
 
 #[cfg(target_os = "macos")]
 pub mod macos;
@@ -8,49 +13,70 @@ pub mod macos;
 #[cfg(target_os = "linux")]
 pub mod linux;
 
-/// Platform-agnostic input event
-#[derive(Debug, Clone)]
-pub enum InputEvent {
-  DirectionPressed(Direction),
-  DirectionReleased(Direction),
-  ModeChanged(Mode),
-  ToggleActive,
-  EmergencyStop,
-}
-
 /// Trait for listening to keyboard input (Thread A)
+///
+/// Backends report raw key events; binding resolution happens in `KeyMapper`.
 pub trait InputListener: Send {
   fn start(&mut self) -> Result<()>;
-  fn next_event(&mut self) -> Result<Option<InputEvent>>;
+  fn next_event(&mut self) -> Result<Option<KeyEvent>>;
   fn stop(&mut self) -> Result<()>;
+
+  /// Exclusively grabs the keyboard so keys stop reaching other clients.
+  /// Backends that cannot grab ignore this.
+  fn set_grab(&mut self, _grab: bool) -> Result<()> {
+    Ok(())
+  }
+
+  /// Hands a key the runtime did not consume back to other clients while grabbed
+  fn pass_through(&mut self, _event: &KeyEvent) -> Result<()> {
+    Ok(())
+  }
 }
 
 /// Trait for moving the mouse cursor (Thread B)
 pub trait CursorActuator: Send {
   fn move_relative(&mut self, delta: Vector2D) -> Result<()>;
+  /// Warps the pointer to `position` in screen pixels
   fn move_absolute(&mut self, position: Vector2D) -> Result<()>;
+  /// Pointer position in screen pixels
   fn get_position(&self) -> Result<Vector2D>;
+  fn press_button(&mut self, button: MouseButton) -> Result<()>;
+  fn release_button(&mut self, button: MouseButton) -> Result<()>;
+  /// Scrolls by `delta` wheel notches; positive y scrolls down, positive x right.
+  /// Fractions of a notch are kept and added to the next call.
+  fn scroll(&mut self, delta: Vector2D) -> Result<()>;
+
+  fn click_button(&mut self, button: MouseButton) -> Result<()> {
+    self.press_button(button)?;
+    self.release_button(button)
+  }
 }
 
-/// Factory function to create platform-specific input listener
-pub fn create_input_listener() -> Result<Box<dyn InputListener>> {
+/// Factory function to create platform-specific input listener; `backend` only matters on Linux
+pub fn create_input_listener(backend: Backend) -> Result<Box<dyn InputListener>> {
   #[cfg(target_os = "macos")]
-  return Ok(Box::new(macos::MacOSInputListener::new()?));
+  return {
+    let _ = backend;
+    Ok(Box::new(macos::MacOSInputListener::new()?))
+  };
 
   #[cfg(target_os = "linux")]
-  return Ok(Box::new(linux::LinuxInputListener::new()?));
+  return linux::create_input_listener(backend);
 
   #[cfg(not(any(target_os = "macos", target_os = "linux")))]
   return Err(crate::Error::Platform("Unsupported platform".to_string()));
 }
 
-/// Factory function to create platform-specific cursor actuator
-pub fn create_cursor_actuator() -> Result<Box<dyn CursorActuator>> {
+/// Factory function to create platform-specific cursor actuator; `backend` only matters on Linux
+pub fn create_cursor_actuator(backend: Backend) -> Result<Box<dyn CursorActuator>> {
   #[cfg(target_os = "macos")]
-  return Ok(Box::new(macos::MacOSCursorActuator::new()?));
+  return {
+    let _ = backend;
+    Ok(Box::new(macos::MacOSCursorActuator::new()?))
+  };
 
   #[cfg(target_os = "linux")]
-  return Ok(Box::new(linux::LinuxCursorActuator::new()?));
+  return linux::create_cursor_actuator(backend);
 
   #[cfg(not(any(target_os = "macos", target_os = "linux")))]
   return Err(crate::Error::Platform("Unsupported platform".to_string()));
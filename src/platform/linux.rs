@@ -1,59 +1,796 @@
-use super::{CursorActuator, InputEvent, InputListener};
-use crate::core::types::Vector2D;
+use super::{CursorActuator, InputListener, KeyEvent};
+use crate::config::Backend;
+use crate::core::keys::ModifierTracker;
+use crate::core::types::{MouseButton, Vector2D};
 use crate::{Error, Result};
+use evdev::uinput::{VirtualDevice, VirtualDeviceBuilder};
+use evdev::{AbsInfo, AbsoluteAxisType, AttributeSet, Device, EventType, Key, RelativeAxisType, UinputAbsSetup};
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::io;
+use std::os::fd::AsRawFd;
 
+pub mod x11;
+
+/// Name of the uinput keyboard used to re-emit keys while grabbed
+const VIRTUAL_KEYBOARD_NAME: &str = "kact virtual keyboard";
+
+/// Keyboard listener reading EV_KEY events straight from `/dev/input/event*`.
+///
+/// Works without a display server (console, Wayland, X11 alike), but needs read
+/// access to the input nodes, usually through membership in the `input` group.
+/// While grabbed, keys are withheld from every other client and the ones kact
+/// does not consume are re-emitted through a uinput keyboard.
 pub struct LinuxInputListener {
-  // TODO: Implement using X11 or evdev
+  devices: Vec<Device>,
+  modifiers: ModifierTracker,
+  pending: VecDeque<KeyEvent>,
+  /// Grab state requested by the runtime, applied once no key is held
+  want_grab: bool,
+  grabbed: bool,
+  virtual_keyboard: Option<VirtualDevice>,
+  /// Keys currently held down on the virtual keyboard
+  forwarded: HashSet<u16>,
 }
 
 impl LinuxInputListener {
   pub fn new() -> Result<Self> {
-    Ok(Self {})
+    let devices: Vec<Device> = evdev::enumerate()
+      .filter(|(_, device)| Self::is_keyboard(device) && device.name() != Some(VIRTUAL_KEYBOARD_NAME))
+      .map(|(path, device)| {
+        tracing::info!(
+          "Found keyboard {} ({})",
+          path.display(),
+          device.name().unwrap_or("unnamed")
+        );
+        device
+      })
+      .collect();
+
+    if devices.is_empty() {
+      return Err(Error::Platform(
+        "No readable keyboard found under /dev/input (is the user in the input group?)".to_string(),
+      ));
+    }
+
+    Ok(Self {
+      devices,
+      modifiers: ModifierTracker::new(),
+      pending: VecDeque::new(),
+      want_grab: false,
+      grabbed: false,
+      virtual_keyboard: None,
+      forwarded: HashSet::new(),
+    })
+  }
+
+  fn is_keyboard(device: &Device) -> bool {
+    device
+      .supported_keys()
+      .is_some_and(|keys| keys.contains(Key::KEY_A) && keys.contains(Key::KEY_Z) && keys.contains(Key::KEY_SPACE))
+  }
+
+  fn set_nonblocking(device: &Device) -> io::Result<()> {
+    let fd = device.as_raw_fd();
+    // SAFETY: fd is a valid descriptor owned by `device` for the duration of the calls
+    unsafe {
+      let flags = libc::fcntl(fd, libc::F_GETFL);
+      if flags < 0 || libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) < 0 {
+        return Err(io::Error::last_os_error());
+      }
+    }
+    Ok(())
+  }
+
+  fn poll_devices(&mut self) -> Result<()> {
+    let mut keys = Vec::new();
+    let mut repeats = Vec::new();
+    let mut lost = Vec::new();
+
+    for (index, device) in self.devices.iter_mut().enumerate() {
+      match device.fetch_events() {
+        Ok(events) => {
+          for event in events.filter(|event| event.event_type() == EventType::KEY) {
+            // value: 0 = release, 1 = press, 2 = autorepeat
+            match event.value() {
+              2 => repeats.push(event.code()),
+              value => keys.push((Key::new(event.code()), value == 1)),
+            }
+          }
+        }
+        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+        // Typically ENODEV: the keyboard was unplugged or went to sleep
+        Err(e) => lost.push((index, e)),
+      }
+    }
+
+    for (index, e) in lost.into_iter().rev() {
+      let mut device = self.devices.remove(index);
+      tracing::warn!(
+        "Dropping keyboard {} after read error: {}",
+        device.name().unwrap_or("unnamed"),
+        e
+      );
+      if self.grabbed {
+        // Usually fails on a vanished device, which is released anyway
+        let _ = device.ungrab();
+      }
+    }
+    if self.devices.is_empty() {
+      return Err(Error::Platform("No keyboard left to read from".to_string()));
+    }
+
+    // The grab swallows the kernel's autorepeat, so repeat the keys we forwarded
+    // ourselves; keys the runtime consumed never repeat
+    if let Some(keyboard) = self.virtual_keyboard.as_mut().filter(|_| self.grabbed) {
+      for code in repeats.into_iter().filter(|code| self.forwarded.contains(code)) {
+        if let Err(e) = keyboard.emit(&[evdev::InputEvent::new(EventType::KEY, code, 2)]) {
+          tracing::warn!("Failed to repeat forwarded key: {}", e);
+        }
+      }
+    }
+
+    for (key, pressed) in keys {
+      // Unnamed keys are still reported so that they can be passed through
+      let name = key_name(key).map_or_else(|| format!("{:?}", key).to_ascii_lowercase(), str::to_string);
+      let modifiers = self.modifiers.update(&name, pressed);
+      self.pending.push_back(KeyEvent {
+        key: name,
+        code: key.code() as u32,
+        pressed,
+        modifiers,
+      });
+    }
+
+    Ok(())
+  }
+
+  fn build_virtual_keyboard(&self) -> io::Result<VirtualDevice> {
+    let mut keys = AttributeSet::<Key>::new();
+    for device in &self.devices {
+      if let Some(supported) = device.supported_keys() {
+        for key in supported.iter() {
+          keys.insert(key);
+        }
+      }
+    }
+
+    VirtualDeviceBuilder::new()?
+      .name(VIRTUAL_KEYBOARD_NAME)
+      .with_keys(&keys)?
+      .build()
+  }
+
+  fn any_key_held(&self) -> Result<bool> {
+    for device in &self.devices {
+      let state = device
+        .get_key_state()
+        .map_err(|e| Error::Platform(format!("Failed to query key state: {}", e)))?;
+      if state.iter().next().is_some() {
+        return Ok(true);
+      }
+    }
+    Ok(false)
+  }
+
+  /// Brings the device grab in line with `want_grab`.
+  ///
+  /// Only runs with an empty queue, so every queued event was read under the
+  /// current grab state. Grabbing also waits for all keys to be up: a key grabbed
+  /// mid-press would never deliver its release to the compositor.
+  fn sync_grab(&mut self) -> Result<()> {
+    if self.want_grab == self.grabbed || !self.pending.is_empty() {
+      return Ok(());
+    }
+
+    if self.want_grab {
+      if self.any_key_held()? {
+        return Ok(());
+      }
+      if self.virtual_keyboard.is_none() {
+        match self.build_virtual_keyboard() {
+          Ok(keyboard) => self.virtual_keyboard = Some(keyboard),
+          Err(e) => {
+            self.want_grab = false;
+            return Err(Error::Platform(format!("Failed to create uinput keyboard: {}", e)));
+          }
+        }
+      }
+      for index in 0..self.devices.len() {
+        if let Err(e) = self.devices[index].grab() {
+          // Don't leave some keyboards grabbed; another client (EBUSY) may hold this one
+          for device in &mut self.devices[..index] {
+            let _ = device.ungrab();
+          }
+          self.want_grab = false;
+          return Err(Error::Platform(format!("Failed to grab keyboard: {}", e)));
+        }
+      }
+      self.grabbed = true;
+      tracing::debug!("Keyboard grabbed");
+    } else {
+      self.release_grab();
+    }
+
+    Ok(())
+  }
+
+  /// Ungrabs every device and lifts any key still held on the virtual keyboard
+  fn release_grab(&mut self) {
+    for device in &mut self.devices {
+      if let Err(e) = device.ungrab() {
+        tracing::warn!("Failed to ungrab keyboard: {}", e);
+      }
+    }
+    self.grabbed = false;
+
+    if let Some(keyboard) = self.virtual_keyboard.as_mut() {
+      for code in self.forwarded.drain() {
+        if let Err(e) = keyboard.emit(&[evdev::InputEvent::new(EventType::KEY, code, 0)]) {
+          tracing::warn!("Failed to release forwarded key: {}", e);
+        }
+      }
+    }
+    tracing::debug!("Keyboard released");
   }
 }
 
 impl InputListener for LinuxInputListener {
   fn start(&mut self) -> Result<()> {
-    // TODO: Set up X11 event monitoring or evdev
-    Err(Error::Platform("Linux input listener not yet implemented".to_string()))
+    for device in &self.devices {
+      Self::set_nonblocking(device).map_err(|e| Error::Platform(format!("Failed to configure input device: {}", e)))?;
+    }
+    Ok(())
   }
 
-  fn next_event(&mut self) -> Result<Option<InputEvent>> {
-    // TODO: Poll for next input event
-    Ok(None)
+  fn next_event(&mut self) -> Result<Option<KeyEvent>> {
+    if self.pending.is_empty() {
+      self.sync_grab()?;
+      self.poll_devices()?;
+    }
+    Ok(self.pending.pop_front())
   }
 
   fn stop(&mut self) -> Result<()> {
-    // TODO: Clean up
+    if self.grabbed {
+      self.release_grab();
+    }
+    self.devices.clear();
+    self.pending.clear();
+    Ok(())
+  }
+
+  fn set_grab(&mut self, grab: bool) -> Result<()> {
+    self.want_grab = grab;
+    self.sync_grab()
+  }
+
+  fn pass_through(&mut self, event: &KeyEvent) -> Result<()> {
+    if !self.grabbed {
+      // Not grabbed: the compositor has already seen this key
+      return Ok(());
+    }
+    let Some(keyboard) = self.virtual_keyboard.as_mut() else {
+      return Ok(());
+    };
+
+    let code = event.code as u16;
+    keyboard
+      .emit(&[evdev::InputEvent::new(EventType::KEY, code, event.pressed as i32)])
+      .map_err(|e| Error::Platform(format!("Failed to forward key: {}", e)))?;
+
+    if event.pressed {
+      self.forwarded.insert(code);
+    } else {
+      self.forwarded.remove(&code);
+    }
     Ok(())
   }
 }
 
+impl Drop for LinuxInputListener {
+  fn drop(&mut self) {
+    // Never leave the keyboard grabbed or a forwarded key stuck down, even when unwinding
+    if self.grabbed {
+      self.release_grab();
+    }
+  }
+}
+
+/// Range of the absolute axes on the virtual tablet device
+const ABS_RANGE: i32 = 65535;
+
+/// High-resolution wheel units per notch, as defined by the kernel
+const WHEEL_HI_RES_UNITS: i32 = 120;
+
+/// Cursor actuator backed by uinput virtual devices.
+///
+/// Relative motion, buttons and the wheel go through a virtual mouse and
+/// absolute warps through a separate virtual tablet (ABS_X/ABS_Y), since libinput
+/// does not accept both axis kinds on one device. Works under X11, Wayland and the
+/// console, but needs write access to `/dev/uinput`.
 pub struct LinuxCursorActuator {
-  // TODO: Implement using X11 XTest extension
+  pointer: VirtualDevice,
+  tablet: VirtualDevice,
+  /// Output size in pixels, which the compositor stretches the tablet over
+  output_size: Option<Vector2D>,
+  /// Pointer position in pixels, as far as kact's own motion tells
+  position: Vector2D,
+  /// Buttons currently held down on the virtual mouse
+  held_buttons: HashSet<MouseButton>,
+  /// Fractions of a high-resolution unit not yet emitted
+  scroll_remainder: Vector2D,
+  /// High-resolution units emitted since the last whole notch, per axis
+  wheel_progress: (i32, i32),
 }
 
 impl LinuxCursorActuator {
   pub fn new() -> Result<Self> {
-    Ok(Self {})
+    let pointer =
+      Self::build_pointer().map_err(|e| Error::Platform(format!("Failed to create uinput pointer: {}", e)))?;
+    let tablet = Self::build_tablet().map_err(|e| Error::Platform(format!("Failed to create uinput tablet: {}", e)))?;
+
+    let output_size = x11::screen_size().or_else(drm_output_size);
+    match output_size {
+      Some(size) => tracing::debug!("Output size {}x{}", size.x, size.y),
+      None => tracing::warn!("Cannot tell the output size, absolute warps are disabled"),
+    }
+
+    Ok(Self {
+      pointer,
+      tablet,
+      output_size,
+      position: Vector2D::zero(),
+      held_buttons: HashSet::new(),
+      scroll_remainder: Vector2D::zero(),
+      wheel_progress: (0, 0),
+    })
+  }
+
+  fn build_pointer() -> io::Result<VirtualDevice> {
+    let mut axes = AttributeSet::<RelativeAxisType>::new();
+    axes.insert(RelativeAxisType::REL_X);
+    axes.insert(RelativeAxisType::REL_Y);
+    axes.insert(RelativeAxisType::REL_WHEEL);
+    axes.insert(RelativeAxisType::REL_HWHEEL);
+    axes.insert(RelativeAxisType::REL_WHEEL_HI_RES);
+    axes.insert(RelativeAxisType::REL_HWHEEL_HI_RES);
+
+    let mut buttons = AttributeSet::<Key>::new();
+    for button in MouseButton::ALL {
+      buttons.insert(button_key(button));
+    }
+
+    VirtualDeviceBuilder::new()?
+      .name("kact virtual pointer")
+      .with_relative_axes(&axes)?
+      .with_keys(&buttons)?
+      .build()
+  }
+
+  fn emit_button(&mut self, button: MouseButton, pressed: bool) -> Result<()> {
+    self
+      .pointer
+      .emit(&[evdev::InputEvent::new(
+        EventType::KEY,
+        button_key(button).code(),
+        pressed as i32,
+      )])
+      .map_err(|e| Error::Platform(format!("Failed to emit button {:?}: {}", button, e)))?;
+
+    if pressed {
+      self.held_buttons.insert(button);
+    } else {
+      self.held_buttons.remove(&button);
+    }
+    Ok(())
+  }
+
+  fn build_tablet() -> io::Result<VirtualDevice> {
+    let info = AbsInfo::new(0, 0, ABS_RANGE, 0, 0, 0);
+    let mut buttons = AttributeSet::<Key>::new();
+    buttons.insert(Key::BTN_LEFT);
+
+    VirtualDeviceBuilder::new()?
+      .name("kact virtual tablet")
+      .with_absolute_axis(&UinputAbsSetup::new(AbsoluteAxisType::ABS_X, info))?
+      .with_absolute_axis(&UinputAbsSetup::new(AbsoluteAxisType::ABS_Y, info))?
+      .with_keys(&buttons)?
+      .build()
   }
 }
 
 impl CursorActuator for LinuxCursorActuator {
   fn move_relative(&mut self, delta: Vector2D) -> Result<()> {
-    // TODO: Use XTestFakeRelativeMotionEvent
-    tracing::trace!("move_relative: ({}, {})", delta.x, delta.y);
+    let dx = delta.x.round() as i32;
+    let dy = delta.y.round() as i32;
+    if dx == 0 && dy == 0 {
+      return Ok(());
+    }
+
+    self
+      .pointer
+      .emit(&[
+        evdev::InputEvent::new(EventType::RELATIVE, RelativeAxisType::REL_X.0, dx),
+        evdev::InputEvent::new(EventType::RELATIVE, RelativeAxisType::REL_Y.0, dy),
+      ])
+      .map_err(|e| Error::Platform(format!("Failed to emit relative motion: {}", e)))?;
+
+    self.position = self.position.add(&Vector2D::new(dx as f64, dy as f64));
     Ok(())
   }
 
+  /// Scales `position` from pixels onto the tablet's `[0, ABS_RANGE]` axes
   fn move_absolute(&mut self, position: Vector2D) -> Result<()> {
-    // TODO: Use XTestFakeMotionEvent
-    tracing::trace!("move_absolute: ({}, {})", position.x, position.y);
+    let size = self
+      .output_size
+      .ok_or_else(|| Error::Platform("Output size unknown, cannot warp the pointer".to_string()))?;
+    let position = Vector2D::new(position.x.clamp(0.0, size.x - 1.0), position.y.clamp(0.0, size.y - 1.0));
+    // Aim at the middle of the pixel so rounding in the compositor cannot land on a neighbour
+    let to_tablet =
+      |pixel: f64, extent: f64| (((pixel.round() + 0.5) / extent * (ABS_RANGE + 1) as f64) as i32).clamp(0, ABS_RANGE);
+    let x = to_tablet(position.x, size.x);
+    let y = to_tablet(position.y, size.y);
+
+    self
+      .tablet
+      .emit(&[
+        evdev::InputEvent::new(EventType::ABSOLUTE, AbsoluteAxisType::ABS_X.0, x),
+        evdev::InputEvent::new(EventType::ABSOLUTE, AbsoluteAxisType::ABS_Y.0, y),
+      ])
+      .map_err(|e| Error::Platform(format!("Failed to emit absolute motion: {}", e)))?;
+
+    self.position = Vector2D::new(position.x.round(), position.y.round());
     Ok(())
   }
 
+  /// uinput is write-only, so this is the position accumulated from emitted motion
   fn get_position(&self) -> Result<Vector2D> {
-    // TODO: Use XQueryPointer
-    Ok(Vector2D::zero())
+    Ok(self.position)
+  }
+
+  fn press_button(&mut self, button: MouseButton) -> Result<()> {
+    self.emit_button(button, true)
+  }
+
+  fn release_button(&mut self, button: MouseButton) -> Result<()> {
+    self.emit_button(button, false)
+  }
+
+  /// Emits high-resolution wheel events, plus a legacy REL_WHEEL/REL_HWHEEL
+  /// event each time a whole notch has accumulated for clients that ignore them
+  fn scroll(&mut self, delta: Vector2D) -> Result<()> {
+    let units = self.scroll_remainder.add(&delta.scale(WHEEL_HI_RES_UNITS as f64));
+    let (dx, dy) = (units.x.trunc(), units.y.trunc());
+    self.scroll_remainder = Vector2D::new(units.x - dx, units.y - dy);
+    let (dx, dy) = (dx as i32, dy as i32);
+    if dx == 0 && dy == 0 {
+      return Ok(());
+    }
+
+    let (progress_x, progress_y) = (self.wheel_progress.0 + dx, self.wheel_progress.1 + dy);
+    let (notches_x, notches_y) = (progress_x / WHEEL_HI_RES_UNITS, progress_y / WHEEL_HI_RES_UNITS);
+    self.wheel_progress = (
+      progress_x - notches_x * WHEEL_HI_RES_UNITS,
+      progress_y - notches_y * WHEEL_HI_RES_UNITS,
+    );
+
+    // The wheel axis counts up when scrolling up, against our y-down convention
+    let mut events = vec![
+      evdev::InputEvent::new(EventType::RELATIVE, RelativeAxisType::REL_HWHEEL_HI_RES.0, dx),
+      evdev::InputEvent::new(EventType::RELATIVE, RelativeAxisType::REL_WHEEL_HI_RES.0, -dy),
+    ];
+    if notches_x != 0 {
+      events.push(evdev::InputEvent::new(
+        EventType::RELATIVE,
+        RelativeAxisType::REL_HWHEEL.0,
+        notches_x,
+      ));
+    }
+    if notches_y != 0 {
+      events.push(evdev::InputEvent::new(
+        EventType::RELATIVE,
+        RelativeAxisType::REL_WHEEL.0,
+        -notches_y,
+      ));
+    }
+
+    self
+      .pointer
+      .emit(&events)
+      .map_err(|e| Error::Platform(format!("Failed to emit scroll: {}", e)))
+  }
+}
+
+impl Drop for LinuxCursorActuator {
+  fn drop(&mut self) {
+    let held: Vec<MouseButton> = self.held_buttons.iter().copied().collect();
+    for button in held {
+      if let Err(e) = self.release_button(button) {
+        tracing::warn!("{}", e);
+      }
+    }
+  }
+}
+
+/// Preferred mode of the first connected DRM output, for when no X display is reachable
+fn drm_output_size() -> Option<Vector2D> {
+  let mut connectors: Vec<_> = fs::read_dir("/sys/class/drm")
+    .ok()?
+    .flatten()
+    .map(|entry| entry.path())
+    .collect();
+  connectors.sort();
+
+  connectors.into_iter().find_map(|connector| {
+    let status = fs::read_to_string(connector.join("status")).ok()?;
+    if status.trim() != "connected" {
+      return None;
+    }
+    // Modes are listed best first, e.g. "2560x1440" or "1920x1080i"
+    let modes = fs::read_to_string(connector.join("modes")).ok()?;
+    let (width, height) = modes.lines().next()?.split_once('x')?;
+    let height = height.trim_end_matches(|c: char| !c.is_ascii_digit());
+    Some(Vector2D::new(width.parse().ok()?, height.parse().ok()?))
+  })
+}
+
+fn button_key(button: MouseButton) -> Key {
+  match button {
+    MouseButton::Left => Key::BTN_LEFT,
+    MouseButton::Middle => Key::BTN_MIDDLE,
+    MouseButton::Right => Key::BTN_RIGHT,
+    MouseButton::Back => Key::BTN_SIDE,
+    MouseButton::Forward => Key::BTN_EXTRA,
+  }
+}
+
+/// Creates the listener for `backend`; `Auto` tries evdev, falling back to XInput2
+/// when no keyboard is readable
+pub fn create_input_listener(backend: Backend) -> Result<Box<dyn InputListener>> {
+  match backend {
+    Backend::Uinput => Ok(Box::new(LinuxInputListener::new()?)),
+    Backend::X11 => Ok(Box::new(x11::X11InputListener::new()?)),
+    Backend::Auto => match LinuxInputListener::new() {
+      Ok(listener) => Ok(Box::new(listener)),
+      Err(e) => {
+        tracing::warn!("{}; falling back to XInput2", e);
+        Ok(Box::new(x11::X11InputListener::new()?))
+      }
+    },
+  }
+}
+
+/// Creates the actuator for `backend`; `Auto` tries uinput, falling back to XTest
+/// when uinput is unavailable
+pub fn create_cursor_actuator(backend: Backend) -> Result<Box<dyn CursorActuator>> {
+  match backend {
+    Backend::Uinput => Ok(Box::new(LinuxCursorActuator::new()?)),
+    Backend::X11 => Ok(Box::new(x11::X11CursorActuator::new()?)),
+    Backend::Auto => match LinuxCursorActuator::new() {
+      Ok(actuator) => Ok(Box::new(actuator)),
+      Err(e) => {
+        tracing::warn!("{}; falling back to XTest", e);
+        Ok(Box::new(x11::X11CursorActuator::new()?))
+      }
+    },
+  }
+}
+
+/// Maps an evdev key code to its key name
+fn key_name(key: Key) -> Option<&'static str> {
+  let name = match key {
+    Key::KEY_A => "a",
+    Key::KEY_B => "b",
+    Key::KEY_C => "c",
+    Key::KEY_D => "d",
+    Key::KEY_E => "e",
+    Key::KEY_F => "f",
+    Key::KEY_G => "g",
+    Key::KEY_H => "h",
+    Key::KEY_I => "i",
+    Key::KEY_J => "j",
+    Key::KEY_K => "k",
+    Key::KEY_L => "l",
+    Key::KEY_M => "m",
+    Key::KEY_N => "n",
+    Key::KEY_O => "o",
+    Key::KEY_P => "p",
+    Key::KEY_Q => "q",
+    Key::KEY_R => "r",
+    Key::KEY_S => "s",
+    Key::KEY_T => "t",
+    Key::KEY_U => "u",
+    Key::KEY_V => "v",
+    Key::KEY_W => "w",
+    Key::KEY_X => "x",
+    Key::KEY_Y => "y",
+    Key::KEY_Z => "z",
+    Key::KEY_0 => "0",
+    Key::KEY_1 => "1",
+    Key::KEY_2 => "2",
+    Key::KEY_3 => "3",
+    Key::KEY_4 => "4",
+    Key::KEY_5 => "5",
+    Key::KEY_6 => "6",
+    Key::KEY_7 => "7",
+    Key::KEY_8 => "8",
+    Key::KEY_9 => "9",
+    Key::KEY_F1 => "f1",
+    Key::KEY_F2 => "f2",
+    Key::KEY_F3 => "f3",
+    Key::KEY_F4 => "f4",
+    Key::KEY_F5 => "f5",
+    Key::KEY_F6 => "f6",
+    Key::KEY_F7 => "f7",
+    Key::KEY_F8 => "f8",
+    Key::KEY_F9 => "f9",
+    Key::KEY_F10 => "f10",
+    Key::KEY_F11 => "f11",
+    Key::KEY_F12 => "f12",
+    Key::KEY_SPACE => "space",
+    Key::KEY_ESC => "escape",
+    Key::KEY_ENTER => "return",
+    Key::KEY_TAB => "tab",
+    Key::KEY_BACKSPACE => "backspace",
+    Key::KEY_DELETE => "delete",
+    Key::KEY_INSERT => "insert",
+    Key::KEY_HOME => "home",
+    Key::KEY_END => "end",
+    Key::KEY_PAGEUP => "page_up",
+    Key::KEY_PAGEDOWN => "page_down",
+    Key::KEY_UP => "up",
+    Key::KEY_DOWN => "down",
+    Key::KEY_LEFT => "left",
+    Key::KEY_RIGHT => "right",
+    Key::KEY_MINUS => "minus",
+    Key::KEY_EQUAL => "equal",
+    Key::KEY_LEFTBRACE => "bracketleft",
+    Key::KEY_RIGHTBRACE => "bracketright",
+    Key::KEY_SEMICOLON => "semicolon",
+    Key::KEY_APOSTROPHE => "apostrophe",
+    Key::KEY_GRAVE => "grave",
+    Key::KEY_BACKSLASH => "backslash",
+    Key::KEY_COMMA => "comma",
+    Key::KEY_DOT => "period",
+    Key::KEY_SLASH => "slash",
+    Key::KEY_CAPSLOCK => "caps_lock",
+    Key::KEY_KP0 => "kp_0",
+    Key::KEY_KP1 => "kp_1",
+    Key::KEY_KP2 => "kp_2",
+    Key::KEY_KP3 => "kp_3",
+    Key::KEY_KP4 => "kp_4",
+    Key::KEY_KP5 => "kp_5",
+    Key::KEY_KP6 => "kp_6",
+    Key::KEY_KP7 => "kp_7",
+    Key::KEY_KP8 => "kp_8",
+    Key::KEY_KP9 => "kp_9",
+    Key::KEY_KPPLUS => "kp_add",
+    Key::KEY_KPMINUS => "kp_subtract",
+    Key::KEY_KPASTERISK => "kp_multiply",
+    Key::KEY_KPSLASH => "kp_divide",
+    Key::KEY_KPDOT => "kp_decimal",
+    Key::KEY_KPENTER => "kp_enter",
+    Key::KEY_LEFTSHIFT => "shift_l",
+    Key::KEY_RIGHTSHIFT => "shift_r",
+    Key::KEY_LEFTCTRL => "control_l",
+    Key::KEY_RIGHTCTRL => "control_r",
+    Key::KEY_LEFTALT => "alt_l",
+    Key::KEY_RIGHTALT => "alt_r",
+    Key::KEY_LEFTMETA => "super_l",
+    Key::KEY_RIGHTMETA => "super_r",
+    _ => return None,
+  };
+  Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs::OpenOptions;
+  use std::thread;
+  use std::time::{Duration, Instant};
+
+  /// uinput tests need a writable `/dev/uinput`; without one they are skipped
+  fn uinput_available() -> bool {
+    let available = OpenOptions::new().write(true).open("/dev/uinput").is_ok();
+    if !available {
+      eprintln!("skipping: /dev/uinput is not available");
+    }
+    available
+  }
+
+  #[test]
+  #[ignore = "needs /dev/uinput and read access to /dev/input"]
+  fn listener_reads_keys_from_a_virtual_keyboard() {
+    if !uinput_available() {
+      return;
+    }
+
+    let mut keys = AttributeSet::<Key>::new();
+    for key in [Key::KEY_A, Key::KEY_Z, Key::KEY_SPACE, Key::KEY_W] {
+      keys.insert(key);
+    }
+    let mut keyboard = VirtualDeviceBuilder::new()
+      .unwrap()
+      .name("kact test keyboard")
+      .with_keys(&keys)
+      .unwrap()
+      .build()
+      .unwrap();
+    // Give udev a moment to create the device node
+    thread::sleep(Duration::from_millis(200));
+
+    let mut listener = match LinuxInputListener::new() {
+      Ok(listener) => listener,
+      Err(e) => {
+        eprintln!("skipping: {}", e);
+        return;
+      }
+    };
+    listener.start().unwrap();
+
+    for pressed in [1, 0] {
+      keyboard
+        .emit(&[evdev::InputEvent::new(EventType::KEY, Key::KEY_W.code(), pressed)])
+        .unwrap();
+    }
+
+    let mut received = Vec::new();
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while received.len() < 2 && Instant::now() < deadline {
+      match listener.next_event().unwrap() {
+        Some(event) if event.key == "w" => received.push(event.pressed),
+        Some(_) => {}
+        None => thread::sleep(Duration::from_millis(5)),
+      }
+    }
+    assert_eq!(received, vec![true, false]);
+  }
+
+  #[test]
+  #[ignore = "needs /dev/uinput and read access to /dev/input"]
+  fn actuator_events_can_be_read_back() {
+    if !uinput_available() {
+      return;
+    }
+
+    let mut actuator = LinuxCursorActuator::new().unwrap();
+    thread::sleep(Duration::from_millis(200));
+    let Some(mut pointer) = evdev::enumerate()
+      .map(|(_, device)| device)
+      .find(|device| device.name() == Some("kact virtual pointer"))
+    else {
+      eprintln!("skipping: cannot open the virtual pointer under /dev/input");
+      return;
+    };
+    LinuxInputListener::set_nonblocking(&pointer).unwrap();
+
+    actuator.move_relative(Vector2D::new(3.0, -2.0)).unwrap();
+    actuator.click_button(MouseButton::Left).unwrap();
+
+    let mut received = Vec::new();
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while received.len() < 4 && Instant::now() < deadline {
+      match pointer.fetch_events() {
+        Ok(events) => received.extend(
+          events
+            .filter(|event| event.event_type() != EventType::SYNCHRONIZATION)
+            .map(|event| (event.event_type(), event.code(), event.value())),
+        ),
+        Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(5)),
+        Err(e) => panic!("{}", e),
+      }
+    }
+    assert_eq!(
+      received,
+      vec![
+        (EventType::RELATIVE, RelativeAxisType::REL_X.0, 3),
+        (EventType::RELATIVE, RelativeAxisType::REL_Y.0, -2),
+        (EventType::KEY, Key::BTN_LEFT.code(), 1),
+        (EventType::KEY, Key::BTN_LEFT.code(), 0),
+      ]
+    );
+    assert_eq!(actuator.get_position().unwrap(), Vector2D::new(3.0, -2.0));
   }
 }
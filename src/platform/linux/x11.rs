@@ -0,0 +1,490 @@
+use crate::core::keys::ModifierTracker;
+use crate::core::types::{MouseButton, Vector2D};
+use crate::platform::{CursorActuator, InputListener, KeyEvent};
+use crate::{Error, Result};
+use std::collections::{HashSet, VecDeque};
+use std::ffi::CStr;
+use std::os::raw::{c_int, c_uint, c_ulong};
+use std::ptr;
+use x11::{xinput2, xlib, xtest};
+
+fn open_display() -> Result<*mut xlib::Display> {
+  // SAFETY: a null name makes Xlib read $DISPLAY; the result is checked before use
+  let display = unsafe { xlib::XOpenDisplay(ptr::null()) };
+  if display.is_null() {
+    return Err(Error::Platform("Cannot open X display (is DISPLAY set?)".to_string()));
+  }
+  Ok(display)
+}
+
+/// Size of the default X screen in pixels, if a display can be opened
+pub(super) fn screen_size() -> Option<Vector2D> {
+  let display = open_display().ok()?;
+  // SAFETY: display is valid and not used after closing
+  unsafe {
+    let screen = xlib::XDefaultScreen(display);
+    let size = Vector2D::new(
+      xlib::XDisplayWidth(display, screen) as f64,
+      xlib::XDisplayHeight(display, screen) as f64,
+    );
+    xlib::XCloseDisplay(display);
+    Some(size)
+  }
+}
+
+/// Global keyboard listener built on XInput2 raw key events.
+///
+/// Raw events reach the root window regardless of focus or grabs, so this needs
+/// neither root nor `input` group membership, only access to the X display.
+pub struct X11InputListener {
+  display: *mut xlib::Display,
+  root: xlib::Window,
+  xi_opcode: c_int,
+  modifiers: ModifierTracker,
+  pending: VecDeque<KeyEvent>,
+}
+
+// SAFETY: the display connection is owned exclusively by this listener and only
+// ever used from the thread that currently owns it
+unsafe impl Send for X11InputListener {}
+
+impl X11InputListener {
+  pub fn new() -> Result<Self> {
+    let display = open_display()?;
+
+    let (mut xi_opcode, mut event_base, mut error_base) = (0, 0, 0);
+    // SAFETY: display is valid and the extension name is a NUL-terminated literal
+    let has_xi = unsafe {
+      xlib::XQueryExtension(
+        display,
+        c"XInputExtension".as_ptr(),
+        &mut xi_opcode,
+        &mut event_base,
+        &mut error_base,
+      )
+    } != 0;
+
+    // Raw events are delivered to the root window since XI 2.1
+    let (mut major, mut minor) = (2, 1);
+    // SAFETY: display is valid
+    let has_xi2 =
+      has_xi && unsafe { xinput2::XIQueryVersion(display, &mut major, &mut minor) } == xlib::Success as c_int;
+    if !has_xi2 {
+      // SAFETY: display is valid and not used after closing
+      unsafe { xlib::XCloseDisplay(display) };
+      return Err(Error::Platform("X server does not support XInput 2.1".to_string()));
+    }
+
+    // SAFETY: display is a valid connection
+    let root = unsafe { xlib::XDefaultRootWindow(display) };
+
+    Ok(Self {
+      display,
+      root,
+      xi_opcode,
+      modifiers: ModifierTracker::new(),
+      pending: VecDeque::new(),
+    })
+  }
+
+  fn select_raw_keys(&self, enable: bool) -> Result<()> {
+    let mut mask = [0u8; 4];
+    if enable {
+      xinput2::XISetMask(&mut mask, xinput2::XI_RawKeyPress);
+      xinput2::XISetMask(&mut mask, xinput2::XI_RawKeyRelease);
+    }
+    let mut event_mask = xinput2::XIEventMask {
+      deviceid: xinput2::XIAllMasterDevices,
+      mask_len: mask.len() as c_int,
+      mask: mask.as_mut_ptr(),
+    };
+
+    // SAFETY: display and root are valid, event_mask points at a live buffer
+    let status = unsafe { xinput2::XISelectEvents(self.display, self.root, &mut event_mask, 1) };
+    if status != xlib::Success as c_int {
+      return Err(Error::Platform("Failed to select XInput2 raw key events".to_string()));
+    }
+    // SAFETY: display is valid
+    unsafe { xlib::XFlush(self.display) };
+    Ok(())
+  }
+
+  fn poll_display(&mut self) {
+    // SAFETY: display is valid; every cookie fetched with XGetEventData is freed
+    unsafe {
+      while xlib::XPending(self.display) > 0 {
+        let mut event: xlib::XEvent = std::mem::zeroed();
+        xlib::XNextEvent(self.display, &mut event);
+
+        let mut cookie = event.generic_event_cookie;
+        if cookie.type_ != xlib::GenericEvent || cookie.extension != self.xi_opcode {
+          continue;
+        }
+        if xlib::XGetEventData(self.display, &mut cookie) == 0 {
+          continue;
+        }
+
+        let pressed = match cookie.evtype {
+          xinput2::XI_RawKeyPress => Some(true),
+          xinput2::XI_RawKeyRelease => Some(false),
+          _ => None,
+        };
+        if let Some(pressed) = pressed {
+          let raw = &*(cookie.data as *const xinput2::XIRawEvent);
+          let repeat = raw.flags & xinput2::XIKeyRepeat != 0;
+          if !repeat && let Some(name) = self.key_name(raw.detail) {
+            let modifiers = self.modifiers.update(&name, pressed);
+            self.pending.push_back(KeyEvent {
+              key: name,
+              code: raw.detail as u32,
+              pressed,
+              modifiers,
+            });
+          }
+        }
+
+        xlib::XFreeEventData(self.display, &mut cookie);
+      }
+    }
+  }
+
+  /// Maps a keycode to its key name via the unshifted keysym
+  fn key_name(&self, keycode: c_int) -> Option<String> {
+    let keycode = u8::try_from(keycode).ok()?;
+    // SAFETY: display is valid; XKeysymToString returns a static string or null
+    let name = unsafe {
+      let keysym = xlib::XkbKeycodeToKeysym(self.display, keycode, 0, 0);
+      let name = xlib::XKeysymToString(keysym);
+      if name.is_null() {
+        return None;
+      }
+      CStr::from_ptr(name).to_string_lossy().to_ascii_lowercase()
+    };
+    Some(normalize_keysym(&name).to_string())
+  }
+}
+
+impl InputListener for X11InputListener {
+  fn start(&mut self) -> Result<()> {
+    self.select_raw_keys(true)
+  }
+
+  fn next_event(&mut self) -> Result<Option<KeyEvent>> {
+    if self.pending.is_empty() {
+      self.poll_display();
+    }
+    Ok(self.pending.pop_front())
+  }
+
+  fn stop(&mut self) -> Result<()> {
+    self.pending.clear();
+    self.select_raw_keys(false)
+  }
+}
+
+impl Drop for X11InputListener {
+  fn drop(&mut self) {
+    // SAFETY: display was opened in new() and is not used after this point
+    unsafe { xlib::XCloseDisplay(self.display) };
+  }
+}
+
+/// Folds keysym names onto the names produced by the evdev backend, so that
+/// bindings behave the same under both listeners
+fn normalize_keysym(name: &str) -> &str {
+  match name {
+    "prior" => "page_up",
+    "next" => "page_down",
+    // Keypad keys report their navigation keysym at level 0 (NumLock off)
+    "kp_insert" => "kp_0",
+    "kp_end" => "kp_1",
+    "kp_down" => "kp_2",
+    "kp_next" | "kp_page_down" => "kp_3",
+    "kp_left" => "kp_4",
+    "kp_begin" => "kp_5",
+    "kp_right" => "kp_6",
+    "kp_home" => "kp_7",
+    "kp_up" => "kp_8",
+    "kp_prior" | "kp_page_up" => "kp_9",
+    "kp_delete" => "kp_decimal",
+    "iso_level3_shift" => "alt_r",
+    other => other,
+  }
+}
+
+/// Cursor actuator driving the X server through the XTest extension.
+///
+/// Needs no uinput access, which makes it the backend of choice under Xvfb and on
+/// X11 desktops where `/dev/uinput` is locked down.
+pub struct X11CursorActuator {
+  display: *mut xlib::Display,
+  root: xlib::Window,
+  held_buttons: HashSet<MouseButton>,
+  /// Scrolled notches not yet sent as a wheel click
+  scroll_remainder: Vector2D,
+}
+
+// SAFETY: the display connection is owned exclusively by this actuator and only
+// ever used from the thread that currently owns it
+unsafe impl Send for X11CursorActuator {}
+
+impl X11CursorActuator {
+  pub fn new() -> Result<Self> {
+    let display = open_display()?;
+
+    let (mut event_base, mut error_base, mut major, mut minor) = (0, 0, 0, 0);
+    // SAFETY: display is a valid connection
+    let has_xtest =
+      unsafe { xtest::XTestQueryExtension(display, &mut event_base, &mut error_base, &mut major, &mut minor) } != 0;
+    if !has_xtest {
+      // SAFETY: display is valid and not used after closing
+      unsafe { xlib::XCloseDisplay(display) };
+      return Err(Error::Platform(
+        "X server does not support the XTest extension".to_string(),
+      ));
+    }
+
+    // SAFETY: display is a valid connection
+    let root = unsafe { xlib::XDefaultRootWindow(display) };
+    tracing::debug!("Using XTest {}.{}", major, minor);
+
+    Ok(Self {
+      display,
+      root,
+      held_buttons: HashSet::new(),
+      scroll_remainder: Vector2D::zero(),
+    })
+  }
+
+  fn flush(&self) {
+    // SAFETY: display is valid for the lifetime of self
+    unsafe { xlib::XFlush(self.display) };
+  }
+
+  fn fake_button_event(&self, number: c_uint, pressed: bool) -> Result<()> {
+    // SAFETY: display is valid; a delay of 0 sends the event immediately
+    let ok = unsafe { xtest::XTestFakeButtonEvent(self.display, number, pressed as c_int, 0) };
+    if ok == 0 {
+      return Err(Error::Platform(format!(
+        "XTestFakeButtonEvent failed for button {}",
+        number
+      )));
+    }
+    Ok(())
+  }
+
+  fn fake_button(&mut self, button: MouseButton, pressed: bool) -> Result<()> {
+    self.fake_button_event(button_number(button), pressed)?;
+    self.flush();
+
+    if pressed {
+      self.held_buttons.insert(button);
+    } else {
+      self.held_buttons.remove(&button);
+    }
+    Ok(())
+  }
+}
+
+impl CursorActuator for X11CursorActuator {
+  fn move_relative(&mut self, delta: Vector2D) -> Result<()> {
+    let dx = delta.x.round() as c_int;
+    let dy = delta.y.round() as c_int;
+    if dx == 0 && dy == 0 {
+      return Ok(());
+    }
+
+    // SAFETY: display is valid; screen -1 means the screen the pointer is on
+    let ok = unsafe { xtest::XTestFakeRelativeMotionEvent(self.display, -1, dx, dy, 0) };
+    if ok == 0 {
+      return Err(Error::Platform("XTestFakeRelativeMotionEvent failed".to_string()));
+    }
+    self.flush();
+    Ok(())
+  }
+
+  fn move_absolute(&mut self, position: Vector2D) -> Result<()> {
+    let x = position.x.round() as c_int;
+    let y = position.y.round() as c_int;
+
+    // SAFETY: display is valid; screen -1 means the screen the pointer is on
+    let ok = unsafe { xtest::XTestFakeMotionEvent(self.display, -1, x, y, 0) };
+    if ok == 0 {
+      return Err(Error::Platform("XTestFakeMotionEvent failed".to_string()));
+    }
+    self.flush();
+    Ok(())
+  }
+
+  fn get_position(&self) -> Result<Vector2D> {
+    let (mut root_return, mut child_return): (c_ulong, c_ulong) = (0, 0);
+    let (mut root_x, mut root_y, mut win_x, mut win_y): (c_int, c_int, c_int, c_int) = (0, 0, 0, 0);
+    let mut mask: c_uint = 0;
+
+    // SAFETY: display and root are valid, all out-pointers reference live locals
+    let on_screen = unsafe {
+      xlib::XQueryPointer(
+        self.display,
+        self.root,
+        &mut root_return,
+        &mut child_return,
+        &mut root_x,
+        &mut root_y,
+        &mut win_x,
+        &mut win_y,
+        &mut mask,
+      )
+    } != 0;
+    if !on_screen {
+      return Err(Error::Platform("Pointer is not on the default screen".to_string()));
+    }
+
+    Ok(Vector2D::new(root_x as f64, root_y as f64))
+  }
+
+  fn press_button(&mut self, button: MouseButton) -> Result<()> {
+    self.fake_button(button, true)
+  }
+
+  fn release_button(&mut self, button: MouseButton) -> Result<()> {
+    self.fake_button(button, false)
+  }
+
+  /// The core protocol only knows whole wheel clicks (buttons 4 to 7), so
+  /// fractions of a notch are accumulated until they add up to one
+  fn scroll(&mut self, delta: Vector2D) -> Result<()> {
+    let total = self.scroll_remainder.add(&delta);
+    let (notches_x, notches_y) = (total.x.trunc(), total.y.trunc());
+    self.scroll_remainder = Vector2D::new(total.x - notches_x, total.y - notches_y);
+
+    let (up_or_down, clicks_y) = if notches_y < 0.0 {
+      (4, -notches_y)
+    } else {
+      (5, notches_y)
+    };
+    let (left_or_right, clicks_x) = if notches_x < 0.0 {
+      (6, -notches_x)
+    } else {
+      (7, notches_x)
+    };
+    for (number, clicks) in [(up_or_down, clicks_y as u32), (left_or_right, clicks_x as u32)] {
+      for _ in 0..clicks {
+        self.fake_button_event(number, true)?;
+        self.fake_button_event(number, false)?;
+      }
+    }
+
+    if notches_x != 0.0 || notches_y != 0.0 {
+      self.flush();
+    }
+    Ok(())
+  }
+}
+
+/// X core protocol button number; 4 to 7 are taken by the scroll wheel
+fn button_number(button: MouseButton) -> c_uint {
+  match button {
+    MouseButton::Left => 1,
+    MouseButton::Middle => 2,
+    MouseButton::Right => 3,
+    MouseButton::Back => 8,
+    MouseButton::Forward => 9,
+  }
+}
+
+impl Drop for X11CursorActuator {
+  fn drop(&mut self) {
+    let held: Vec<MouseButton> = self.held_buttons.iter().copied().collect();
+    for button in held {
+      if let Err(e) = self.release_button(button) {
+        tracing::warn!("{}", e);
+      }
+    }
+    // SAFETY: display was opened in new() and is not used after this point
+    unsafe { xlib::XCloseDisplay(self.display) };
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::key_name;
+  use super::*;
+  use crate::core::keys::KEY_NAMES;
+  use evdev::Key;
+  use std::thread;
+  use std::time::{Duration, Instant};
+
+  /// Keysyms a US layout reports at level 0 where they differ from the evdev name
+  const X_KEYSYMS: &[(Key, &str)] = &[
+    (Key::KEY_PAGEUP, "prior"),
+    (Key::KEY_PAGEDOWN, "next"),
+    (Key::KEY_KP0, "kp_insert"),
+    (Key::KEY_KP1, "kp_end"),
+    (Key::KEY_KP2, "kp_down"),
+    (Key::KEY_KP3, "kp_next"),
+    (Key::KEY_KP4, "kp_left"),
+    (Key::KEY_KP5, "kp_begin"),
+    (Key::KEY_KP6, "kp_right"),
+    (Key::KEY_KP7, "kp_home"),
+    (Key::KEY_KP8, "kp_up"),
+    (Key::KEY_KP9, "kp_prior"),
+    (Key::KEY_KPDOT, "kp_delete"),
+    (Key::KEY_RIGHTALT, "iso_level3_shift"),
+  ];
+
+  #[test]
+  fn keysym_names_agree_with_evdev_names() {
+    let evdev_names: Vec<(Key, &str)> = (0..0x300)
+      .map(Key::new)
+      .filter_map(|key| key_name(key).map(|name| (key, name)))
+      .collect();
+
+    for name in KEY_NAMES {
+      let count = evdev_names.iter().filter(|(_, evdev)| evdev == name).count();
+      assert_eq!(count, 1, "evdev reports '{}' for {} keys", name, count);
+    }
+    for (key, name) in evdev_names {
+      assert!(KEY_NAMES.contains(&name), "'{}' is not a known key name", name);
+      let keysym = X_KEYSYMS
+        .iter()
+        .find(|(x_key, _)| *x_key == key)
+        .map_or(name, |(_, keysym)| keysym);
+      assert_eq!(normalize_keysym(keysym), name, "keysym '{}' for {:?}", keysym, key);
+    }
+  }
+
+  #[test]
+  #[ignore = "needs an X server with XInput2 and XTest, e.g. Xvfb"]
+  fn listener_reads_injected_keys() {
+    if std::env::var_os("DISPLAY").is_none() {
+      eprintln!("skipping: DISPLAY is not set");
+      return;
+    }
+
+    let mut listener = X11InputListener::new().unwrap();
+    listener.start().unwrap();
+
+    let display = open_display().unwrap();
+    // SAFETY: display is valid and closed at the end; the keysym name is a NUL-terminated literal
+    unsafe {
+      let keycode = xlib::XKeysymToKeycode(display, xlib::XStringToKeysym(c"w".as_ptr()));
+      for pressed in [1, 0] {
+        xtest::XTestFakeKeyEvent(display, keycode as c_uint, pressed, 0);
+      }
+      xlib::XFlush(display);
+    }
+
+    let mut received = Vec::new();
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while received.len() < 2 && Instant::now() < deadline {
+      match listener.next_event().unwrap() {
+        Some(event) if event.key == "w" => received.push(event.pressed),
+        Some(_) => {}
+        None => thread::sleep(Duration::from_millis(5)),
+      }
+    }
+    // SAFETY: display is not used after closing
+    unsafe { xlib::XCloseDisplay(display) };
+    assert_eq!(received, vec![true, false]);
+  }
+}
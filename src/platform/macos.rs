@@ -1,5 +1,5 @@
-use super::{CursorActuator, InputEvent, InputListener};
-use crate::core::types::Vector2D;
+use super::{CursorActuator, InputListener, KeyEvent};
+use crate::core::types::{MouseButton, Vector2D};
 use crate::{Error, Result};
 
 pub struct MacOSInputListener {
@@ -18,7 +18,7 @@ impl InputListener for MacOSInputListener {
     Err(Error::Platform("MacOS input listener not yet implemented".to_string()))
   }
 
-  fn next_event(&mut self) -> Result<Option<InputEvent>> {
+  fn next_event(&mut self) -> Result<Option<KeyEvent>> {
     // TODO: Poll for next input event
     Ok(None)
   }
@@ -57,4 +57,22 @@ impl CursorActuator for MacOSCursorActuator {
     // TODO: Use CGEventGetLocation
     Ok(Vector2D::zero())
   }
+
+  fn press_button(&mut self, button: MouseButton) -> Result<()> {
+    // TODO: Post kCGEventLeftMouseDown and friends via CGEventCreateMouseEvent
+    tracing::trace!("press_button: {:?}", button);
+    Ok(())
+  }
+
+  fn release_button(&mut self, button: MouseButton) -> Result<()> {
+    // TODO: Post kCGEventLeftMouseUp and friends via CGEventCreateMouseEvent
+    tracing::trace!("release_button: {:?}", button);
+    Ok(())
+  }
+
+  fn scroll(&mut self, delta: Vector2D) -> Result<()> {
+    // TODO: Use CGEventCreateScrollWheelEvent with kCGScrollEventUnitPixel
+    tracing::trace!("scroll: ({}, {})", delta.x, delta.y);
+    Ok(())
+  }
 }
@@ -0,0 +1,194 @@
+use super::CursorActuator;
+use crate::Result;
+use crate::core::types::{MouseButton, Vector2D};
+
+/// Carries the fractional part of relative motion over to later frames.
+///
+/// Backends can only move whole pixels, so slow motion would otherwise be
+/// rounded away every frame.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SubpixelAccumulator {
+  residual: Vector2D,
+}
+
+impl SubpixelAccumulator {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds `delta` and takes out the whole pixels accumulated so far.
+  ///
+  /// An axis that reverses direction drops its leftover fraction, so turning
+  /// around responds immediately instead of first paying back the old residual.
+  pub fn accumulate(&mut self, delta: Vector2D) -> Vector2D {
+    let carry = |residual: f64, delta: f64| {
+      if residual * delta < 0.0 {
+        delta
+      } else {
+        residual + delta
+      }
+    };
+    let total = Vector2D::new(carry(self.residual.x, delta.x), carry(self.residual.y, delta.y));
+
+    let whole = Vector2D::new(total.x.trunc(), total.y.trunc());
+    self.residual = Vector2D::new(total.x - whole.x, total.y - whole.y);
+    whole
+  }
+
+  /// Puts back whole pixels taken out by `accumulate` that never reached the screen
+  pub fn restore(&mut self, whole: Vector2D) {
+    self.residual = self.residual.add(&whole);
+  }
+
+  pub fn reset(&mut self) {
+    self.residual = Vector2D::zero();
+  }
+}
+
+/// Actuator wrapper that only passes whole-pixel relative motion to the backend
+pub struct SubpixelActuator {
+  inner: Box<dyn CursorActuator>,
+  accumulator: SubpixelAccumulator,
+}
+
+impl SubpixelActuator {
+  pub fn new(inner: Box<dyn CursorActuator>) -> Self {
+    Self {
+      inner,
+      accumulator: SubpixelAccumulator::new(),
+    }
+  }
+}
+
+impl CursorActuator for SubpixelActuator {
+  fn move_relative(&mut self, delta: Vector2D) -> Result<()> {
+    let whole = self.accumulator.accumulate(delta);
+    if whole.x == 0.0 && whole.y == 0.0 {
+      return Ok(());
+    }
+    let result = self.inner.move_relative(whole);
+    if result.is_err() {
+      self.accumulator.restore(whole);
+    }
+    result
+  }
+
+  /// A warp lands on an exact position, so leftover fractions no longer apply
+  fn move_absolute(&mut self, position: Vector2D) -> Result<()> {
+    self.accumulator.reset();
+    self.inner.move_absolute(position)
+  }
+
+  fn get_position(&self) -> Result<Vector2D> {
+    self.inner.get_position()
+  }
+
+  fn press_button(&mut self, button: MouseButton) -> Result<()> {
+    self.inner.press_button(button)
+  }
+
+  fn release_button(&mut self, button: MouseButton) -> Result<()> {
+    self.inner.release_button(button)
+  }
+
+  fn scroll(&mut self, delta: Vector2D) -> Result<()> {
+    self.inner.scroll(delta)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Error;
+  use std::sync::{Arc, Mutex};
+
+  /// Records relative moves, failing them while `failing` is set
+  #[derive(Default)]
+  struct Recorder {
+    moves: Arc<Mutex<Vec<Vector2D>>>,
+    failing: Arc<Mutex<bool>>,
+  }
+
+  impl CursorActuator for Recorder {
+    fn move_relative(&mut self, delta: Vector2D) -> Result<()> {
+      if *self.failing.lock().unwrap() {
+        return Err(Error::Platform("device gone".to_string()));
+      }
+      self.moves.lock().unwrap().push(delta);
+      Ok(())
+    }
+
+    fn move_absolute(&mut self, _position: Vector2D) -> Result<()> {
+      Ok(())
+    }
+
+    fn get_position(&self) -> Result<Vector2D> {
+      Ok(Vector2D::zero())
+    }
+
+    fn press_button(&mut self, _button: MouseButton) -> Result<()> {
+      Ok(())
+    }
+
+    fn release_button(&mut self, _button: MouseButton) -> Result<()> {
+      Ok(())
+    }
+
+    fn scroll(&mut self, _delta: Vector2D) -> Result<()> {
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn small_deltas_add_up() {
+    let mut accumulator = SubpixelAccumulator::new();
+    let mut total = Vector2D::zero();
+    for _ in 0..1000 {
+      total = total.add(&accumulator.accumulate(Vector2D::new(0.3, -0.3)));
+    }
+
+    // Nothing is lost: what was emitted plus what is pending is the full 300 px
+    let conserved = total.add(&accumulator.residual);
+    assert!((conserved.x - 300.0).abs() < 1e-9 && (conserved.y + 300.0).abs() < 1e-9);
+    assert!(accumulator.residual.x.abs() < 1.0 && accumulator.residual.y.abs() < 1.0);
+    assert_eq!(total, Vector2D::new(total.x.trunc(), total.y.trunc()));
+    assert!(total.x >= 299.0 && total.y <= -299.0);
+  }
+
+  #[test]
+  fn reversal_drops_residual() {
+    let mut accumulator = SubpixelAccumulator::new();
+    assert_eq!(accumulator.accumulate(Vector2D::new(0.7, 0.0)), Vector2D::zero());
+    // The 0.7 px left over is not paid back before moving the other way
+    assert_eq!(accumulator.accumulate(Vector2D::new(-0.7, 0.0)), Vector2D::zero());
+    assert_eq!(
+      accumulator.accumulate(Vector2D::new(-0.4, 0.0)),
+      Vector2D::new(-1.0, 0.0)
+    );
+  }
+
+  #[test]
+  fn warp_drops_residual() {
+    let recorder = Recorder::default();
+    let moves = Arc::clone(&recorder.moves);
+    let mut actuator = SubpixelActuator::new(Box::new(recorder));
+
+    actuator.move_relative(Vector2D::new(0.7, 0.7)).unwrap();
+    actuator.move_absolute(Vector2D::new(100.0, 100.0)).unwrap();
+    actuator.move_relative(Vector2D::new(0.7, 0.7)).unwrap();
+    assert!(moves.lock().unwrap().is_empty());
+  }
+
+  #[test]
+  fn failed_move_is_retried() {
+    let recorder = Recorder::default();
+    let (moves, failing) = (Arc::clone(&recorder.moves), Arc::clone(&recorder.failing));
+    let mut actuator = SubpixelActuator::new(Box::new(recorder));
+
+    *failing.lock().unwrap() = true;
+    assert!(actuator.move_relative(Vector2D::new(2.5, 0.0)).is_err());
+    *failing.lock().unwrap() = false;
+    actuator.move_relative(Vector2D::new(0.5, 0.0)).unwrap();
+    assert_eq!(*moves.lock().unwrap(), vec![Vector2D::new(3.0, 0.0)]);
+  }
+}
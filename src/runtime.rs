@@ -1,5 +1,7 @@
 pub mod engine;
+pub mod watchdog;
 pub mod watcher;
 
 pub use engine::Runtime;
+pub use watchdog::Watchdog;
 pub use watcher::ConfigWatcher;
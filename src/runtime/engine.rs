@@ -1,14 +1,16 @@
-use crate::config::Config;
-use crate::core::{AppState, MotionEngine};
-use crate::platform::{InputEvent, create_cursor_actuator, create_input_listener};
+use crate::config::{Backend, Config};
+use crate::core::{AppState, InputEvent, KeyMapper, KeyRoute, MotionEngine, MouseButton};
+use crate::platform::subpixel::SubpixelActuator;
+use crate::platform::{CursorActuator, InputListener, create_cursor_actuator, create_input_listener};
+use crate::runtime::watchdog::Watchdog;
 use crate::{Error, Result};
-use crossbeam_channel::{Receiver, Sender, bounded};
-use std::sync::{Arc, Mutex};
+use crossbeam_channel::{Receiver, Sender, TrySendError, bounded};
+use std::sync::{Arc, Mutex, PoisonError, TryLockError};
 use std::thread;
 use std::time::{Duration, Instant};
 
 pub enum ControlMessage {
-  UpdateConfig(Config),
+  UpdateConfig(Box<Config>),
   Shutdown,
 }
 
@@ -22,15 +24,35 @@ pub struct Runtime {
 impl Runtime {
   pub fn new(config: Config) -> Result<Self> {
     let state = Arc::new(Mutex::new(AppState::new()));
+    let mapper = Arc::new(Mutex::new(KeyMapper::new(&config.keybindings, &config.modes)));
     let config_arc = Arc::new(Mutex::new(config));
     let (control_tx, control_rx) = bounded::<ControlMessage>(10);
     let (event_tx, event_rx) = bounded::<InputEvent>(100);
+    let (grab_timeout_ms, backend) = {
+      let cfg = config_arc.lock().unwrap();
+      (cfg.system.grab_timeout_ms, cfg.system.backend)
+    };
+    let watchdog = Watchdog::new(Duration::from_millis(grab_timeout_ms));
+    watchdog.beat();
 
     // Thread A: input listener (blocking, OS hooks)
-    let input_handle = Self::spawn_input_thread(event_tx)?;
+    let input_handle = Self::spawn_input_thread(
+      Arc::clone(&state),
+      Arc::clone(&mapper),
+      watchdog.clone(),
+      backend,
+      event_tx,
+    )?;
 
     // Thread B: motion engine (non-blocking, pure logic)
-    let motion_handle = Self::spawn_motion_thread(Arc::clone(&state), Arc::clone(&config_arc), event_rx, control_rx)?;
+    let motion_handle = Self::spawn_motion_thread(
+      Arc::clone(&state),
+      Arc::clone(&config_arc),
+      mapper,
+      watchdog,
+      event_rx,
+      control_rx,
+    )?;
 
     Ok(Self {
       state,
@@ -40,11 +62,17 @@ impl Runtime {
     })
   }
 
-  fn spawn_input_thread(event_tx: Sender<InputEvent>) -> Result<thread::JoinHandle<()>> {
+  fn spawn_input_thread(
+    state: Arc<Mutex<AppState>>,
+    mapper: Arc<Mutex<KeyMapper>>,
+    watchdog: Watchdog,
+    backend: Backend,
+    event_tx: Sender<InputEvent>,
+  ) -> Result<thread::JoinHandle<()>> {
     let handle = thread::Builder::new()
       .name("kact-input".to_string())
       .spawn(move || {
-        if let Err(e) = Self::input_thread_main(event_tx) {
+        if let Err(e) = Self::input_thread_main(state, mapper, watchdog, backend, event_tx) {
           tracing::error!("Input thread error: {}", e);
         }
       })
@@ -53,16 +81,81 @@ impl Runtime {
     Ok(handle)
   }
 
-  fn input_thread_main(event_tx: Sender<InputEvent>) -> Result<()> {
-    let mut listener = create_input_listener()?;
+  /// The listener ungrabs itself when dropped, which also covers errors and panics here
+  fn input_thread_main(
+    state: Arc<Mutex<AppState>>,
+    mapper: Arc<Mutex<KeyMapper>>,
+    watchdog: Watchdog,
+    backend: Backend,
+    event_tx: Sender<InputEvent>,
+  ) -> Result<()> {
+    let mut listener = create_input_listener(backend)?;
+    Self::run_input_loop(listener.as_mut(), state, mapper, watchdog, event_tx)
+  }
+
+  /// Runs the listener until the motion thread goes away.
+  ///
+  /// Never blocks on the motion thread: state is read with `try_lock` and events
+  /// are dropped rather than queued when it stops draining them, so the watchdog
+  /// check below always gets a chance to release the keyboard.
+  fn run_input_loop(
+    listener: &mut dyn InputListener,
+    state: Arc<Mutex<AppState>>,
+    mapper: Arc<Mutex<KeyMapper>>,
+    watchdog: Watchdog,
+    event_tx: Sender<InputEvent>,
+  ) -> Result<()> {
     listener.start()?;
 
-    loop {
+    let mut active = false;
+    let mut stalled = false;
+    let mut can_grab = true;
+
+    while !watchdog.is_stopped() {
+      active = match state.try_lock() {
+        Ok(s) => s.active,
+        Err(TryLockError::WouldBlock) => active,
+        Err(TryLockError::Poisoned(_)) => false,
+      };
+
+      if watchdog.is_stalled() != stalled {
+        stalled = !stalled;
+        if stalled {
+          tracing::error!(
+            "Runtime stopped responding for {:?}, releasing keyboard",
+            watchdog.age()
+          );
+        } else {
+          tracing::info!("Runtime responding again");
+        }
+      }
+
+      // Hold the keyboard even while inactive, so the toggle chord itself stays
+      // hidden; the mapper hands every other key back through pass_through.
+      // Without a grab (no uinput access, another client holds the keyboard)
+      // keys still drive the pointer, they just also reach other apps
+      if can_grab && let Err(e) = listener.set_grab(!stalled) {
+        tracing::warn!("Cannot grab the keyboard, keys will also reach other apps: {}", e);
+        can_grab = false;
+      }
+
       match listener.next_event()? {
-        Some(event) => {
-          if event_tx.send(event).is_err() {
-            tracing::warn!("Event channel closed, stopping input listener");
-            break;
+        Some(key_event) => {
+          let route = mapper
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .route(&key_event, active && !stalled);
+          match route {
+            KeyRoute::Consumed(Some(event)) => match event_tx.try_send(event) {
+              Ok(()) => {}
+              Err(TrySendError::Full(_)) => tracing::warn!("Event queue full, dropping input event"),
+              Err(TrySendError::Disconnected(_)) => {
+                tracing::warn!("Event channel closed, stopping input listener");
+                break;
+              }
+            },
+            KeyRoute::Consumed(None) => {}
+            KeyRoute::PassThrough => listener.pass_through(&key_event)?,
           }
         }
         None => {
@@ -78,13 +171,17 @@ impl Runtime {
   fn spawn_motion_thread(
     state: Arc<Mutex<AppState>>,
     config: Arc<Mutex<Config>>,
+    mapper: Arc<Mutex<KeyMapper>>,
+    watchdog: Watchdog,
     event_rx: Receiver<InputEvent>,
     control_rx: Receiver<ControlMessage>,
   ) -> Result<thread::JoinHandle<()>> {
     let handle = thread::Builder::new()
       .name("kact-motion".to_string())
       .spawn(move || {
-        if let Err(e) = Self::motion_thread_main(state, config, event_rx, control_rx) {
+        // Whichever way this thread ends, the input thread releases the keyboard and exits
+        let _stop = watchdog.stop_on_drop();
+        if let Err(e) = Self::motion_thread_main(state, config, mapper, &watchdog, event_rx, control_rx) {
           tracing::error!("Motion thread error: {}", e);
         }
       })
@@ -96,13 +193,19 @@ impl Runtime {
   fn motion_thread_main(
     state: Arc<Mutex<AppState>>,
     config: Arc<Mutex<Config>>,
+    mapper: Arc<Mutex<KeyMapper>>,
+    watchdog: &Watchdog,
     event_rx: Receiver<InputEvent>,
     control_rx: Receiver<ControlMessage>,
   ) -> Result<()> {
-    let mut actuator = create_cursor_actuator()?;
-    let mut engine = {
+    let backend = config.lock().unwrap().system.backend;
+    let mut actuator: Box<dyn CursorActuator> = Box::new(SubpixelActuator::new(create_cursor_actuator(backend)?));
+    let (mut engine, mut scroll_engine) = {
       let cfg = config.lock().unwrap();
-      MotionEngine::new(cfg.motion.clone())
+      (
+        MotionEngine::new(cfg.motion.clone(), &cfg.modes),
+        MotionEngine::new(cfg.scroll.to_motion(&cfg.motion), &cfg.modes.multipliers_only()),
+      )
     };
 
     let target_fps = {
@@ -114,13 +217,24 @@ impl Runtime {
     let mut last_tick = Instant::now();
 
     loop {
+      watchdog.beat();
+
       // Check for control messages (config updates, shutdown)
       if let Ok(msg) = control_rx.try_recv() {
         match msg {
           ControlMessage::UpdateConfig(new_config) => {
             tracing::info!("Hot-reloading configuration");
-            engine.update_config(new_config.motion.clone());
-            *config.lock().unwrap() = new_config;
+            engine.update_config(new_config.motion.clone(), &new_config.modes);
+            scroll_engine.update_config(
+              new_config.scroll.to_motion(&new_config.motion),
+              &new_config.modes.multipliers_only(),
+            );
+            watchdog.set_timeout(Duration::from_millis(new_config.system.grab_timeout_ms));
+            mapper
+              .lock()
+              .unwrap()
+              .update_bindings(&new_config.keybindings, &new_config.modes);
+            *config.lock().unwrap() = *new_config;
           }
           ControlMessage::Shutdown => {
             tracing::info!("Motion thread shutting down");
@@ -131,7 +245,7 @@ impl Runtime {
 
       // Process input events (non-blocking)
       while let Ok(event) = event_rx.try_recv() {
-        Self::handle_input_event(&state, event);
+        Self::handle_input_event(&state, actuator.as_mut(), event);
       }
 
       // Motion tick
@@ -139,23 +253,31 @@ impl Runtime {
       let delta_time = (now - last_tick).as_secs_f64();
       last_tick = now;
 
-      let (new_velocity, delta_position) = {
+      let ((new_motion, delta_position), (new_scroll_motion, delta_scroll)) = {
         let current_state = state.lock().unwrap();
-        engine.tick(&current_state, delta_time)
+        (
+          engine.tick(&current_state, delta_time, now),
+          scroll_engine.tick_scroll(&current_state, delta_time, now),
+        )
       };
 
       // Update state
       {
         let mut s = state.lock().unwrap();
-        s.velocity = new_velocity;
+        s.motion = new_motion;
         s.position = s.position.add(&delta_position);
+        s.scroll_motion = new_scroll_motion;
       }
 
-      // Move cursor (if there's movement)
-      if delta_position.magnitude() > 0.01 {
-        if let Err(e) = actuator.move_relative(delta_position) {
-          tracing::error!("Failed to move cursor: {}", e);
-        }
+      if delta_scroll.magnitude() > 0.0
+        && let Err(e) = actuator.scroll(delta_scroll)
+      {
+        tracing::error!("Failed to scroll: {}", e);
+      }
+
+      // Move cursor; fractions of a pixel are carried over to the next frame
+      if let Err(e) = actuator.move_relative(delta_position) {
+        tracing::error!("Failed to move cursor: {}", e);
       }
 
       // Check emergency stop
@@ -177,7 +299,7 @@ impl Runtime {
     Ok(())
   }
 
-  fn handle_input_event(state: &Arc<Mutex<AppState>>, event: InputEvent) {
+  fn handle_input_event(state: &Arc<Mutex<AppState>>, actuator: &mut dyn CursorActuator, event: InputEvent) {
     let mut s = state.lock().unwrap();
 
     match event {
@@ -188,8 +310,38 @@ impl Runtime {
         s.input.release_direction(dir);
       }
       InputEvent::ModeChanged(mode) => {
+        tracing::info!("Mode changed to {}", mode);
         s.input.set_mode(mode);
-        tracing::info!("Mode changed to {:?}", mode);
+      }
+      InputEvent::ModeHeld(mode) => {
+        tracing::debug!("Holding mode {}", mode);
+        s.input.hold_mode(mode);
+      }
+      InputEvent::ModeReleased(mode) => {
+        s.input.release_mode(&mode);
+        tracing::debug!("Released mode {}, back to {}", mode, s.input.current_mode());
+      }
+      InputEvent::ButtonPressed(button) => {
+        Self::set_button(&mut s, actuator, button, true);
+      }
+      InputEvent::ButtonReleased(button) => {
+        if !s.is_latched(button) {
+          Self::set_button(&mut s, actuator, button, false);
+        }
+      }
+      InputEvent::DragLockToggled => {
+        let locked = !s.drag_locked;
+        s.drag_locked = locked;
+        Self::set_button(&mut s, actuator, MouseButton::Left, locked);
+        tracing::info!("Drag lock: {}", locked);
+      }
+      InputEvent::ScrollModeToggled => {
+        s.scroll_mode = !s.scroll_mode;
+        tracing::info!("Scroll mode: {}", s.scroll_mode);
+      }
+      InputEvent::GlideCancelled => {
+        s.motion.stop();
+        s.scroll_motion.stop();
       }
       InputEvent::ToggleActive => {
         s.toggle_active();
@@ -197,15 +349,39 @@ impl Runtime {
       }
       InputEvent::EmergencyStop => {
         s.trigger_emergency_stop();
+        for button in MouseButton::ALL {
+          if s.held_buttons.contains(&button) {
+            Self::set_button(&mut s, actuator, button, false);
+          }
+        }
         tracing::warn!("Emergency stop activated!");
       }
     }
   }
 
+  /// Presses or releases a button, keeping `AppState::held_buttons` in sync
+  fn set_button(s: &mut AppState, actuator: &mut dyn CursorActuator, button: MouseButton, pressed: bool) {
+    let result = if pressed {
+      actuator.press_button(button)
+    } else {
+      actuator.release_button(button)
+    };
+
+    match result {
+      Ok(()) if pressed => {
+        s.held_buttons.insert(button);
+      }
+      Ok(()) => {
+        s.held_buttons.remove(&button);
+      }
+      Err(e) => tracing::error!("Failed to update {:?} button: {}", button, e),
+    }
+  }
+
   pub fn update_config(&self, config: Config) -> Result<()> {
     self
       .control_tx
-      .send(ControlMessage::UpdateConfig(config))
+      .send(ControlMessage::UpdateConfig(Box::new(config)))
       .map_err(|_| Error::ChannelSend)?;
     Ok(())
   }
@@ -237,3 +413,162 @@ impl Runtime {
     self.state.lock().unwrap().clone()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::platform::KeyEvent;
+
+  /// What the runtime asked of a `FakeListener`
+  #[derive(Debug, Default)]
+  struct Calls {
+    /// Every change of the requested grab
+    grabs: Vec<(Instant, bool)>,
+    grab_attempts: usize,
+    polls: usize,
+    stopped: bool,
+  }
+
+  /// Listener without keys that records the grab the runtime asks for
+  struct FakeListener {
+    calls: Arc<Mutex<Calls>>,
+    /// Fails every grab, like a keyboard already grabbed by another client
+    grab_busy: bool,
+  }
+
+  impl InputListener for FakeListener {
+    fn start(&mut self) -> Result<()> {
+      Ok(())
+    }
+
+    fn next_event(&mut self) -> Result<Option<KeyEvent>> {
+      self.calls.lock().unwrap().polls += 1;
+      Ok(None)
+    }
+
+    fn stop(&mut self) -> Result<()> {
+      self.calls.lock().unwrap().stopped = true;
+      Ok(())
+    }
+
+    fn set_grab(&mut self, grab: bool) -> Result<()> {
+      let mut calls = self.calls.lock().unwrap();
+      calls.grab_attempts += 1;
+      if self.grab_busy {
+        return Err(Error::Platform(
+          "Failed to grab keyboard: Device or resource busy".to_string(),
+        ));
+      }
+      if calls.grabs.last().map(|(_, last)| *last) != Some(grab) {
+        calls.grabs.push((Instant::now(), grab));
+      }
+      Ok(())
+    }
+  }
+
+  /// Runs the input loop on a fake listener in the background
+  fn spawn_input_loop(watchdog: &Watchdog, grab_busy: bool) -> (Arc<Mutex<Calls>>, thread::JoinHandle<Result<()>>) {
+    let calls = Arc::new(Mutex::new(Calls::default()));
+    let mut listener = FakeListener {
+      calls: Arc::clone(&calls),
+      grab_busy,
+    };
+    let mut state = AppState::new();
+    state.active = true;
+    let state = Arc::new(Mutex::new(state));
+    let config = Config::default();
+    let mapper = Arc::new(Mutex::new(KeyMapper::new(&config.keybindings, &config.modes)));
+    let (event_tx, _event_rx) = bounded(100);
+
+    let watchdog = watchdog.clone();
+    let handle = thread::spawn(move || Runtime::run_input_loop(&mut listener, state, mapper, watchdog, event_tx));
+    (calls, handle)
+  }
+
+  fn wait_until(timeout: Duration, condition: impl Fn() -> bool) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+      if condition() {
+        return true;
+      }
+      thread::sleep(Duration::from_millis(1));
+    }
+    condition()
+  }
+
+  #[test]
+  fn stalled_runtime_releases_grab() {
+    let timeout = Duration::from_millis(100);
+    let watchdog = Watchdog::new(timeout);
+    watchdog.beat();
+    let (calls, handle) = spawn_input_loop(&watchdog, false);
+
+    // A beating runtime keeps the keyboard
+    let beating_until = Instant::now() + Duration::from_millis(200);
+    while Instant::now() < beating_until {
+      watchdog.beat();
+      thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(
+      calls
+        .lock()
+        .unwrap()
+        .grabs
+        .iter()
+        .map(|(_, grab)| *grab)
+        .collect::<Vec<_>>(),
+      vec![true]
+    );
+
+    // Once the beats stop, the grab goes within the timeout, give or take a loop iteration
+    watchdog.beat();
+    let last_beat = Instant::now();
+    let released = || calls.lock().unwrap().grabs.last().is_some_and(|(_, grab)| !grab);
+    assert!(wait_until(timeout * 3, released));
+    let (released_at, _) = *calls.lock().unwrap().grabs.last().unwrap();
+    assert!(released_at - last_beat <= timeout + Duration::from_millis(50));
+
+    watchdog.stop();
+    handle.join().unwrap().unwrap();
+    assert!(calls.lock().unwrap().stopped);
+  }
+
+  #[test]
+  fn stop_guard_ends_input_loop_when_motion_thread_panics() {
+    let watchdog = Watchdog::new(Duration::from_secs(60));
+    watchdog.beat();
+    let (calls, handle) = spawn_input_loop(&watchdog, false);
+    assert!(wait_until(Duration::from_secs(1), || !calls
+      .lock()
+      .unwrap()
+      .grabs
+      .is_empty()));
+
+    let guard_watchdog = watchdog.clone();
+    let motion = thread::spawn(move || {
+      let _stop = guard_watchdog.stop_on_drop();
+      panic!("simulated motion thread crash");
+    });
+    assert!(motion.join().is_err());
+
+    assert!(wait_until(Duration::from_secs(1), || handle.is_finished()));
+    handle.join().unwrap().unwrap();
+    assert!(calls.lock().unwrap().stopped);
+  }
+
+  #[test]
+  fn failed_grab_keeps_reading_keys() {
+    let watchdog = Watchdog::new(Duration::from_secs(60));
+    watchdog.beat();
+    let (calls, handle) = spawn_input_loop(&watchdog, true);
+
+    // The loop outlives the failed grab and does not retry it on every poll
+    assert!(wait_until(Duration::from_secs(1), || calls.lock().unwrap().polls > 100));
+    assert!(!handle.is_finished());
+    assert_eq!(calls.lock().unwrap().grab_attempts, 1);
+
+    watchdog.stop();
+    handle.join().unwrap().unwrap();
+    assert!(calls.lock().unwrap().stopped);
+  }
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Liveness signal shared between the motion and input threads.
+///
+/// The motion thread beats once per frame; the input thread refuses to hold an
+/// exclusive keyboard grab once the beats stop for longer than the timeout, so a
+/// stalled or crashed runtime can never leave the user without a keyboard.
+#[derive(Debug, Clone)]
+pub struct Watchdog {
+  inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+  epoch: Instant,
+  /// Milliseconds since `epoch` at the last beat
+  last_beat: AtomicU64,
+  timeout_ms: AtomicU64,
+  stopped: AtomicBool,
+}
+
+impl Watchdog {
+  pub fn new(timeout: Duration) -> Self {
+    Self {
+      inner: Arc::new(Inner {
+        epoch: Instant::now(),
+        last_beat: AtomicU64::new(0),
+        timeout_ms: AtomicU64::new(timeout.as_millis() as u64),
+        stopped: AtomicBool::new(false),
+      }),
+    }
+  }
+
+  fn now_ms(&self) -> u64 {
+    self.inner.epoch.elapsed().as_millis() as u64
+  }
+
+  pub fn beat(&self) {
+    self.inner.last_beat.store(self.now_ms(), Ordering::Release);
+  }
+
+  pub fn set_timeout(&self, timeout: Duration) {
+    self
+      .inner
+      .timeout_ms
+      .store(timeout.as_millis() as u64, Ordering::Release);
+  }
+
+  /// Time since the last beat
+  pub fn age(&self) -> Duration {
+    let last = self.inner.last_beat.load(Ordering::Acquire);
+    Duration::from_millis(self.now_ms().saturating_sub(last))
+  }
+
+  /// Whether the runtime has stopped beating or has been stopped outright
+  pub fn is_stalled(&self) -> bool {
+    self.is_stopped() || self.age().as_millis() as u64 > self.inner.timeout_ms.load(Ordering::Acquire)
+  }
+
+  /// Marks the runtime as gone for good
+  pub fn stop(&self) {
+    self.inner.stopped.store(true, Ordering::Release);
+  }
+
+  pub fn is_stopped(&self) -> bool {
+    self.inner.stopped.load(Ordering::Acquire)
+  }
+
+  /// Returns a guard that stops the watchdog when dropped, including during a panic
+  pub fn stop_on_drop(&self) -> StopGuard {
+    StopGuard(self.clone())
+  }
+}
+
+/// Stops its `Watchdog` when dropped
+pub struct StopGuard(Watchdog);
+
+impl Drop for StopGuard {
+  fn drop(&mut self) {
+    self.0.stop();
+  }
+}